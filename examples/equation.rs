@@ -1,14 +1,14 @@
-use chem_parse::parse;
+use chem_parse::parse_equation;
 use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let string = String::from("4Fe+3O2->2Fe2O");
-    let ast = parse(string)?;
-    // Node: comment broken up into multiple lines
-    // Ast: Equation(
-    //   Reactants([ForumulaUnit(4, [Element(1, "Fe")]), ForumulaUnit(3, [Element(2, "O")])]),
-    //   Products([ForumulaUnit(2, [Element(2, "Fe"), Element(1, "O")])])
-    // )
-    println!("Ast: {:?}", ast);
+    let equation = parse_equation(string)?;
+    // Reactants: 2, products: 1
+    println!(
+        "Reactants: {}, products: {}",
+        equation.reactants().units().len(),
+        equation.products().units().len()
+    );
     Ok(())
 }
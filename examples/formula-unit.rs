@@ -1,10 +1,14 @@
-use chem_parse::parse;
+use chem_parse::parse_formula;
 use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
     let string = String::from("Fe2O3");
-    let ast = parse(string)?;
-    // Ast: ForumulaUnit(1, [Element(2, "Fe"), Element(3, "O")])
-    println!("Ast: {:?}", ast);
+    let unit = parse_formula(string)?;
+    // Coefficient: 1, composition: {"Fe": 2, "O": 3}
+    println!(
+        "Coefficient: {}, composition: {:?}",
+        unit.coefficient(),
+        unit.composition()?
+    );
     Ok(())
 }
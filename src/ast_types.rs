@@ -1,21 +1,630 @@
-// TODO: change Node to be proper types once
-// we get variants as types, eventually
-/// A Node in the AST (abstract syntax tree)
-#[derive(Debug, PartialEq)]
+use crate::error::Span;
+
+/// The root of the AST (abstract syntax tree), either a formula unit or an equation.
+/// Every node has the span of the source it was parsed from, nodes built by hand have an empty span at 0.
+/// Spans are ignored when comparing nodes
+#[derive(Debug, Clone, PartialEq)]
 pub enum Node {
-    /// Chemical elements e.g. O2.
-    /// The number is the subscript, in this case 2
-    Element(u16, String),
-    /// Groups of elements or other groups (Polyatoms) e.g. (2FeO2)4
-    /// The number is the subscript, in this case 4
-    Group(u16, Vec<Node>),
-    /// Forumula Unit, a few elements and/or polyatoms eg 7(NH4)2SO4
-    /// This number is the coeffecient, in this case 7
-    ForumulaUnit(u16, Vec<Node>),
-    /// The reactants side (left) of yields (->)
-    Reactants(Vec<Node>),
-    /// The products side (right) of yeilds (->)
-    Products(Vec<Node>),
-    /// The whole equation
-    Equation(Box<Node>, Box<Node>),
+    /// A single formula unit e.g. 2H2O
+    Formula(FormulaUnit),
+    /// A whole equation e.g. 2H2+O2->2H2O
+    Equation(Equation),
+}
+
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Formula(unit) => unit.span(),
+            Self::Equation(equation) => equation.span(),
+        }
+    }
+}
+
+/// An equation, reactants yielding products e.g. 2H2+O2->2H2O
+#[derive(Debug, Clone)]
+pub struct Equation {
+    reactants: Side,
+    products: Side,
+    arrow: Arrow,
+    above: Vec<Condition>,
+    below: Vec<Condition>,
+    span: Span,
+}
+
+impl Equation {
+    /// An equation with a forward arrow (->)
+    pub fn new(reactants: Side, products: Side) -> Self {
+        Self {
+            reactants,
+            products,
+            arrow: Arrow::Forward,
+            above: vec![],
+            below: vec![],
+            span: Span::default(),
+        }
+    }
+
+    /// A copy with a different arrow between the sides
+    pub fn with_arrow(mut self, arrow: Arrow) -> Self {
+        self.arrow = arrow;
+        self
+    }
+
+    pub fn arrow(&self) -> Arrow {
+        self.arrow
+    }
+
+    /// A copy with conditions written above and below the arrow e.g. -[Fe, 450C]->
+    pub fn with_conditions(mut self, above: Vec<Condition>, below: Vec<Condition>) -> Self {
+        self.above = above;
+        self.below = below;
+        self
+    }
+
+    /// The conditions above the arrow, e.g. Fe and 450C for -[Fe, 450C]->
+    pub fn above(&self) -> &[Condition] {
+        &self.above
+    }
+
+    /// The conditions below the arrow, e.g. H2O for -[][H2O]->
+    pub fn below(&self) -> &[Condition] {
+        &self.below
+    }
+
+    /// Which way the reaction goes, see `Arrow::direction`
+    pub fn direction(&self) -> Direction {
+        self.arrow.direction()
+    }
+
+    /// The left side of the arrow
+    pub fn reactants(&self) -> &Side {
+        &self.reactants
+    }
+
+    /// The right side of the arrow
+    pub fn products(&self) -> &Side {
+        &self.products
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for Equation {
+    fn eq(&self, other: &Self) -> bool {
+        self.reactants == other.reactants
+            && self.products == other.products
+            && self.arrow == other.arrow
+            && self.above == other.above
+            && self.below == other.below
+    }
+}
+
+/// One side of an equation, formula units joined by plus signs e.g. 2H2+O2
+#[derive(Debug, Clone)]
+pub struct Side {
+    units: Vec<FormulaUnit>,
+    span: Span,
+}
+
+impl Side {
+    /// None if there are no formula units
+    pub fn new(units: Vec<FormulaUnit>) -> Option<Self> {
+        if units.is_empty() {
+            return None;
+        }
+        Some(Self {
+            units,
+            span: Span::default(),
+        })
+    }
+
+    pub fn units(&self) -> &[FormulaUnit] {
+        &self.units
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for Side {
+    fn eq(&self, other: &Self) -> bool {
+        self.units == other.units
+    }
+}
+
+/// Forumula Unit, a few elements and/or polyatoms e.g. 7(NH4)2SO4,
+/// or several of them joined as a hydrate or adduct e.g. 2CuSO4·5H2O
+#[derive(Debug, Clone)]
+pub struct FormulaUnit {
+    coefficient: u16,
+    species: Species,
+    state: Option<State>,
+    span: Span,
+}
+
+/// What a formula unit is a number of, either atoms or electrons.
+/// Electrons only exist at this level, so a part or group can never contain one
+#[derive(Debug, Clone, PartialEq)]
+pub enum Species {
+    /// The parts joined by separators, there is only one unless this is an adduct
+    Formula(Vec<Part>),
+    /// Electrons in a half-reaction e.g. 2e-
+    Electron(Electron),
+}
+
+impl FormulaUnit {
+    /// A formula unit with a single part e.g. 2SO4^2-(aq), None if there are no terms
+    pub fn new(
+        coefficient: u16,
+        terms: Vec<Term>,
+        charge: i16,
+        state: Option<State>,
+    ) -> Option<Self> {
+        Self::adduct(coefficient, vec![Part::new(1, terms, charge)?], state)
+    }
+
+    /// A formula unit made of several parts e.g. CuSO4·5H2O, None if there are no parts
+    pub fn adduct(coefficient: u16, parts: Vec<Part>, state: Option<State>) -> Option<Self> {
+        Self::from_species(coefficient, Species::Formula(parts), state)
+    }
+
+    /// Electrons in a half-reaction e.g. 2e-
+    pub fn electron(coefficient: u16) -> Self {
+        Self {
+            coefficient,
+            species: Species::Electron(Electron::new()),
+            state: None,
+            span: Span::default(),
+        }
+    }
+
+    /// None if the species is a formula without parts
+    pub fn from_species(coefficient: u16, species: Species, state: Option<State>) -> Option<Self> {
+        if matches!(&species, Species::Formula(parts) if parts.is_empty()) {
+            return None;
+        }
+        Some(Self {
+            coefficient,
+            species,
+            state,
+            span: Span::default(),
+        })
+    }
+
+    /// A copy of the formula unit with a different coefficient
+    pub fn with_coefficient(mut self, coefficient: u16) -> Self {
+        self.coefficient = coefficient;
+        self
+    }
+
+    /// The coefficient e.g. 7 for 7(NH4)2SO4
+    pub fn coefficient(&self) -> u16 {
+        self.coefficient
+    }
+
+    pub fn species(&self) -> &Species {
+        &self.species
+    }
+
+    /// The parts joined by separators, there is only one unless this is an adduct.
+    /// Electrons have none
+    pub fn parts(&self) -> &[Part] {
+        match &self.species {
+            Species::Formula(parts) => parts,
+            Species::Electron(_) => &[],
+        }
+    }
+
+    /// The state of matter if one was written e.g. (aq)
+    pub fn state(&self) -> Option<State> {
+        self.state
+    }
+
+    /// Whether there is more than one part e.g. CuSO4·5H2O
+    pub fn is_adduct(&self) -> bool {
+        self.parts().len() > 1
+    }
+
+    /// Whether the formula unit is electrons e.g. 2e-
+    pub fn is_electron(&self) -> bool {
+        matches!(self.species, Species::Electron(_))
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for FormulaUnit {
+    fn eq(&self, other: &Self) -> bool {
+        self.coefficient == other.coefficient
+            && self.species == other.species
+            && self.state == other.state
+    }
+}
+
+/// One part of a formula unit, e.g. 5H2O in CuSO4·5H2O.
+/// Most formula units are a single part with a count of 1
+#[derive(Debug, Clone)]
+pub struct Part {
+    count: u16,
+    terms: Vec<Term>,
+    charge: i16,
+    span: Span,
+}
+
+impl Part {
+    /// None if there are no terms
+    pub fn new(count: u16, terms: Vec<Term>, charge: i16) -> Option<Self> {
+        if terms.is_empty() {
+            return None;
+        }
+        Some(Self {
+            count,
+            terms,
+            charge,
+            span: Span::default(),
+        })
+    }
+
+    /// The number written after the separator e.g. 5 for ·5H2O
+    pub fn count(&self) -> u16 {
+        self.count
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    /// The charge written after the terms e.g. -2 for SO4^2-
+    pub fn charge(&self) -> i16 {
+        self.charge
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for Part {
+    fn eq(&self, other: &Self) -> bool {
+        self.count == other.count && self.terms == other.terms && self.charge == other.charge
+    }
+}
+
+/// An element or group inside a formula unit or group
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Element(ElementCount),
+    Group(Group),
+}
+
+/// Groups of elements or other groups (Polyatoms) e.g. (NH4)2
+#[derive(Debug, Clone)]
+pub struct Group {
+    subscript: u16,
+    terms: Vec<Term>,
+    charge: i16,
+    kind: BracketKind,
+    span: Span,
+}
+
+impl Group {
+    /// None if there are no terms
+    pub fn new(subscript: u16, terms: Vec<Term>, charge: i16, kind: BracketKind) -> Option<Self> {
+        if terms.is_empty() {
+            return None;
+        }
+        Some(Self {
+            subscript,
+            terms,
+            charge,
+            kind,
+            span: Span::default(),
+        })
+    }
+
+    /// The subscript e.g. 2 for (NH4)2
+    pub fn subscript(&self) -> u16 {
+        self.subscript
+    }
+
+    pub fn terms(&self) -> &[Term] {
+        &self.terms
+    }
+
+    /// The charge written inside the group e.g. -1 for (NO3^-)
+    pub fn charge(&self) -> i16 {
+        self.charge
+    }
+
+    /// The kind of bracket it was written with
+    pub fn kind(&self) -> BracketKind {
+        self.kind
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for Group {
+    fn eq(&self, other: &Self) -> bool {
+        self.subscript == other.subscript
+            && self.terms == other.terms
+            && self.charge == other.charge
+            && self.kind == other.kind
+    }
+}
+
+/// A chemical element and its subscript e.g. O2
+#[derive(Debug, Clone)]
+pub struct ElementCount {
+    symbol: String,
+    count: u16,
+    span: Span,
+}
+
+impl ElementCount {
+    pub fn new(symbol: impl Into<String>, count: u16) -> Self {
+        Self {
+            symbol: symbol.into(),
+            count,
+            span: Span::default(),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// The subscript e.g. 2 for O2
+    pub fn count(&self) -> u16 {
+        self.count
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for ElementCount {
+    fn eq(&self, other: &Self) -> bool {
+        self.symbol == other.symbol && self.count == other.count
+    }
+}
+
+/// An electron e-, with a charge of -1 and no mass, which half-reactions gain or lose
+#[derive(Debug, Clone, Default)]
+pub struct Electron {
+    span: Span,
+}
+
+impl Electron {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy with the span of the source it was parsed from
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for Electron {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// The kind of bracket a group is written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BracketKind {
+    /// ( )
+    Round,
+    /// [ ] e.g. [Cu(NH3)4]SO4
+    Square,
+    /// { }
+    Curly,
+}
+
+impl BracketKind {
+    pub fn open(&self) -> char {
+        match self {
+            Self::Round => '(',
+            Self::Square => '[',
+            Self::Curly => '{',
+        }
+    }
+
+    pub fn close(&self) -> char {
+        match self {
+            Self::Round => ')',
+            Self::Square => ']',
+            Self::Curly => '}',
+        }
+    }
+}
+
+/// A state of matter, written after a formula unit e.g. H2O(l)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// (s)
+    Solid,
+    /// (l)
+    Liquid,
+    /// (g)
+    Gas,
+    /// (aq)
+    Aqueous,
+}
+
+impl State {
+    /// The symbol written inside the parens e.g. "aq"
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Solid => "s",
+            Self::Liquid => "l",
+            Self::Gas => "g",
+            Self::Aqueous => "aq",
+        }
+    }
+
+    /// The state for a symbol e.g. "aq", if there is one
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "s" => Some(Self::Solid),
+            "l" => Some(Self::Liquid),
+            "g" => Some(Self::Gas),
+            "aq" => Some(Self::Aqueous),
+            _ => None,
+        }
+    }
+}
+
+/// The arrow between the sides of an equation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrow {
+    /// -> or →
+    Forward,
+    /// <- or ←
+    Reverse,
+    /// <-> or ⇄, a reaction that goes both ways
+    Reversible,
+    /// <=> or ⇌
+    Equilibrium,
+    /// =, a stoichiometric relation
+    Equals,
+}
+
+impl Arrow {
+    /// The arrow in the plain syntax e.g. "<=>"
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Forward => "->",
+            Self::Reverse => "<-",
+            Self::Reversible => "<->",
+            Self::Equilibrium => "<=>",
+            Self::Equals => "=",
+        }
+    }
+
+    /// The arrow split where its conditions go e.g. ("<=", "=>") for <=[Fe]=>
+    pub fn halves(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Forward => ("-", "->"),
+            Self::Reverse => ("<-", "-"),
+            Self::Reversible => ("<-", "->"),
+            Self::Equilibrium => ("<=", "=>"),
+            Self::Equals => ("=", "="),
+        }
+    }
+
+    /// The unicode character for the arrow e.g. '⇌'
+    pub fn unicode(&self) -> char {
+        match self {
+            Self::Forward => '→',
+            Self::Reverse => '←',
+            Self::Reversible => '⇄',
+            Self::Equilibrium => '⇌',
+            Self::Equals => '=',
+        }
+    }
+
+    /// Which way a reaction with this arrow goes.
+    /// A reversible reaction is treated as one at equilibrium, and `=` as going forward
+    pub fn direction(&self) -> Direction {
+        match self {
+            Self::Forward | Self::Equals => Direction::Forward,
+            Self::Reverse => Direction::Reverse,
+            Self::Reversible | Self::Equilibrium => Direction::Equilibrium,
+        }
+    }
+}
+
+/// Something written above or below the arrow of an equation e.g. a catalyst or a temperature
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// A species e.g. Fe, which does not take part in the reaction
+    Species(FormulaUnit),
+    /// LaTeX markup e.g. `\Delta` for heat, with the span it was parsed from.
+    /// Text is markup when it contains a command, a backslash followed by a letter
+    Markup(String, Span),
+    /// Anything else e.g. 450C, with the span it was parsed from
+    Text(String, Span),
+}
+
+impl Condition {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Species(unit) => unit.span(),
+            Self::Markup(_, span) | Self::Text(_, span) => *span,
+        }
+    }
+}
+
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Species(l_unit), Self::Species(r_unit)) => l_unit == r_unit,
+            (Self::Markup(l_markup, _), Self::Markup(r_markup, _)) => l_markup == r_markup,
+            (Self::Text(l_text, _), Self::Text(r_text, _)) => l_text == r_text,
+            _ => false,
+        }
+    }
+}
+
+/// Which way a reaction goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Reactants to products
+    Forward,
+    /// Products to reactants
+    Reverse,
+    /// Both ways at once
+    Equilibrium,
 }
@@ -0,0 +1,785 @@
+use crate::{
+    ast_types::{Condition, Equation, FormulaUnit, Node, Side},
+    composition::{accumulate, unit_charge, Composition, CompositionError},
+};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The reasons an equation can not be balanced
+#[derive(Debug, PartialEq)]
+pub enum BalanceError {
+    /// The node is not a `Node::Equation`
+    NotAnEquation,
+    /// No set of positive coefficients balances the equation
+    Impossible,
+    /// There are this many independent solutions, so the coefficients are ambiguous
+    MultipleSolutions(usize),
+    /// The smallest coefficients do not fit in a u16
+    CoefficientOverflow,
+    /// A formula unit could not be counted
+    Composition(CompositionError),
+    /// Half-reactions can only be combined when one gains the electrons the other loses
+    ElectronsDoNotCancel,
+    /// Half-reactions can only be combined when they have the same arrow
+    MismatchedArrows,
+    /// Combining half-reactions cancelled out every species on one side, e.g. a reaction and
+    /// its reverse
+    SideCancelled,
+}
+
+impl Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAnEquation => write!(f, "Only equations can be balanced"),
+            Self::Impossible => write!(f, "Equation can not be balanced"),
+            Self::MultipleSolutions(count) => {
+                write!(f, "Equation has {} independent solutions", count)
+            }
+            Self::CoefficientOverflow => write!(f, "Balanced coefficients are too large"),
+            Self::Composition(error) => write!(f, "{}", error),
+            Self::ElectronsDoNotCancel => {
+                write!(f, "Half-reactions must gain and lose electrons")
+            }
+            Self::MismatchedArrows => write!(f, "Half-reactions must have the same arrow"),
+            Self::SideCancelled => write!(f, "Every species on one side cancelled out"),
+        }
+    }
+}
+
+impl Error for BalanceError {}
+
+/// Find the smallest positive integer coefficients which balance an equation,
+/// and return a copy of it with those coefficients.
+/// The existing coefficients are ignored.
+pub fn balance(node: &Node) -> Result<Node, BalanceError> {
+    match node {
+        Node::Equation(equation) => equation.balance().map(Node::Equation),
+        _ => Err(BalanceError::NotAnEquation),
+    }
+}
+
+fn balance_equation(equation: &Equation) -> Result<Equation, BalanceError> {
+    let reactants = equation.reactants().units();
+    let products = equation.products().units();
+
+    let units: Vec<&FormulaUnit> = reactants.iter().chain(products).collect();
+    let compositions = units
+        .iter()
+        .map(|unit| unit_composition(unit))
+        .collect::<Result<Vec<_>, _>>()?;
+    let charges = units
+        .iter()
+        .map(|unit| unit_charge(unit))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(BalanceError::Composition)?;
+
+    let mut symbols: Vec<&String> = compositions.iter().flat_map(|map| map.keys()).collect();
+    symbols.sort();
+    symbols.dedup();
+
+    // one row per element, one column per formula unit, products negated
+    let mut rows: Vec<Vec<i128>> = symbols
+        .iter()
+        .map(|symbol| {
+            compositions
+                .iter()
+                .map(|map| *map.get(*symbol).unwrap_or(&0) as i128)
+                .collect()
+        })
+        .collect();
+    // charge is conserved too, which only matters for ions
+    if charges.iter().any(|charge| *charge != 0) {
+        rows.push(charges.iter().map(|charge| *charge as i128).collect());
+    }
+    let matrix = rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .enumerate()
+                .map(|(column, count)| {
+                    if column < reactants.len() {
+                        Ratio::from(count)
+                    } else {
+                        Ratio::from(-count)
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    let coefficients = nullspace_vector(matrix, units.len())?;
+
+    let mut coefficients = coefficients.into_iter();
+    // the sides keep their formula units, so they are never empty
+    let mut rebuild = |units: &[FormulaUnit]| -> Side {
+        Side::new(
+            units
+                .iter()
+                .map(|unit| unit.clone().with_coefficient(coefficients.next().unwrap()))
+                .collect(),
+        )
+        .unwrap()
+    };
+    let reactants = rebuild(reactants);
+    let products = rebuild(products);
+
+    Ok(Equation::new(reactants, products)
+        .with_arrow(equation.arrow())
+        .with_conditions(equation.above().to_vec(), equation.below().to_vec()))
+}
+
+/// Combine a reduction and an oxidation half-reaction into one redox equation,
+/// e.g. Fe^2+->Fe^3++e- and MnO4^-+H^++e-->Mn^2++H2O into
+/// 5Fe^2++MnO4^-+8H^+->5Fe^3++Mn^2++4H2O.
+/// Both are balanced first and scaled so that the electrons cancel,
+/// then species on both sides, like the electrons, are cancelled out and the coefficients reduced.
+/// The half-reactions must have the same arrow, which the result keeps along with their conditions
+pub fn combine(first: &Node, second: &Node) -> Result<Node, BalanceError> {
+    match (first, second) {
+        (Node::Equation(first), Node::Equation(second)) => {
+            first.combine(second).map(Node::Equation)
+        }
+        _ => Err(BalanceError::NotAnEquation),
+    }
+}
+
+fn combine_equations(first: &Equation, second: &Equation) -> Result<Equation, BalanceError> {
+    if first.arrow() != second.arrow() {
+        return Err(BalanceError::MismatchedArrows);
+    }
+    let (first, second) = (balance_equation(first)?, balance_equation(second)?);
+    let (first_gained, second_gained) = (electrons_gained(&first), electrons_gained(&second));
+    if first_gained * second_gained >= 0 {
+        return Err(BalanceError::ElectronsDoNotCancel);
+    }
+    let divisor = gcd(first_gained as i128, second_gained as i128)
+        .ok_or(BalanceError::CoefficientOverflow)? as i64;
+
+    // the net coefficient of each species, products positive and reactants negative
+    let mut species: Vec<(FormulaUnit, i64)> = vec![];
+    for (equation, multiplier) in [
+        (&first, second_gained.abs() / divisor),
+        (&second, first_gained.abs() / divisor),
+    ] {
+        let reactants = equation.reactants().units().iter().map(|unit| (unit, -1));
+        let products = equation.products().units().iter().map(|unit| (unit, 1));
+        for (unit, sign) in reactants.chain(products) {
+            let count = sign * multiplier * unit.coefficient() as i64;
+            let unit = unit.clone().with_coefficient(1);
+            match species.iter_mut().find(|(other, _)| *other == unit) {
+                Some((_, total)) => *total += count,
+                None => species.push((unit, count)),
+            }
+        }
+    }
+
+    species.retain(|(_, count)| *count != 0);
+    let divisor = species
+        .iter()
+        .try_fold(0, |acc, (_, count)| gcd(acc, *count as i128))
+        .ok_or(BalanceError::CoefficientOverflow)? as i64;
+    for (_, count) in species.iter_mut() {
+        *count /= divisor;
+    }
+
+    let side = |sign: i64| -> Result<Side, BalanceError> {
+        let units = species
+            .iter()
+            .filter(|(_, count)| count * sign > 0)
+            .map(|(unit, count)| {
+                u16::try_from(count.abs())
+                    .map(|coefficient| unit.clone().with_coefficient(coefficient))
+                    .map_err(|_| BalanceError::CoefficientOverflow)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Side::new(units).ok_or(BalanceError::SideCancelled)
+    };
+    let conditions = |first: &[Condition], second: &[Condition]| {
+        let mut conditions = first.to_vec();
+        for condition in second {
+            if !conditions.contains(condition) {
+                conditions.push(condition.clone());
+            }
+        }
+        conditions
+    };
+    Ok(Equation::new(side(-1)?, side(1)?)
+        .with_arrow(first.arrow())
+        .with_conditions(
+            conditions(first.above(), second.above()),
+            conditions(first.below(), second.below()),
+        ))
+}
+
+/// The electrons a half-reaction gains, negative when it loses them
+fn electrons_gained(equation: &Equation) -> i64 {
+    let electrons = |side: &Side| -> i64 {
+        side.units()
+            .iter()
+            .filter(|unit| unit.is_electron())
+            .map(|unit| unit.coefficient() as i64)
+            .sum()
+    };
+    electrons(equation.reactants()) - electrons(equation.products())
+}
+
+/// The atom counts of one element on both sides of an equation
+#[derive(Debug, PartialEq)]
+pub struct ElementBalance {
+    pub symbol: String,
+    pub reactants: u32,
+    pub products: u32,
+}
+
+impl ElementBalance {
+    /// The products count minus the reactants count
+    pub fn difference(&self) -> i64 {
+        self.products as i64 - self.reactants as i64
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.reactants == self.products
+    }
+}
+
+/// The atom counts of every element in an equation, ordered by symbol,
+/// and the net charge of each side. It is balanced when both match
+#[derive(Debug, PartialEq)]
+pub struct BalanceReport {
+    pub elements: Vec<ElementBalance>,
+    pub reactants_charge: i32,
+    pub products_charge: i32,
+}
+
+impl BalanceReport {
+    /// Whether every element has the same count and both sides have the same net charge
+    pub fn is_balanced(&self) -> bool {
+        self.elements.iter().all(ElementBalance::is_balanced)
+            && self.reactants_charge == self.products_charge
+    }
+
+    /// The elements which have a different count on each side
+    pub fn unbalanced(&self) -> impl Iterator<Item = &ElementBalance> {
+        self.elements
+            .iter()
+            .filter(|element| !element.is_balanced())
+    }
+}
+
+/// Count the atoms of each element and the net charge on both sides of an equation,
+/// using its current coefficients
+pub fn report(node: &Node) -> Result<BalanceReport, BalanceError> {
+    match node {
+        Node::Equation(equation) => equation.balance_report(),
+        _ => Err(BalanceError::NotAnEquation),
+    }
+}
+
+fn equation_report(equation: &Equation) -> Result<BalanceReport, BalanceError> {
+    let reactants = equation
+        .reactants()
+        .composition()
+        .map_err(BalanceError::Composition)?;
+    let products = equation
+        .products()
+        .composition()
+        .map_err(BalanceError::Composition)?;
+
+    let mut symbols: Vec<&String> = reactants.keys().chain(products.keys()).collect();
+    symbols.sort();
+    symbols.dedup();
+
+    Ok(BalanceReport {
+        elements: symbols
+            .into_iter()
+            .map(|symbol| ElementBalance {
+                symbol: symbol.clone(),
+                reactants: *reactants.get(symbol).unwrap_or(&0),
+                products: *products.get(symbol).unwrap_or(&0),
+            })
+            .collect(),
+        reactants_charge: equation
+            .reactants()
+            .net_charge()
+            .map_err(BalanceError::Composition)?,
+        products_charge: equation
+            .products()
+            .net_charge()
+            .map_err(BalanceError::Composition)?,
+    })
+}
+
+/// The composition of a single formula unit, ignoring its coefficient
+fn unit_composition(unit: &FormulaUnit) -> Result<Composition, BalanceError> {
+    let mut map = Composition::new();
+    accumulate(unit, 1, &mut map).map_err(BalanceError::Composition)?;
+    Ok(map)
+}
+
+/// Reduce the matrix to row echelon form and return the single vector spanning its nullspace,
+/// scaled to the smallest positive integers
+fn nullspace_vector(mut matrix: Vec<Vec<Ratio>>, columns: usize) -> Result<Vec<u16>, BalanceError> {
+    let mut pivots = vec![];
+    let mut row = 0;
+    for column in 0..columns {
+        let Some(pivot) = (row..matrix.len()).find(|&r| !matrix[r][column].is_zero()) else {
+            continue;
+        };
+        matrix.swap(row, pivot);
+
+        let divisor = matrix[row][column];
+        for value in matrix[row].iter_mut() {
+            *value = value
+                .checked_div(divisor)
+                .ok_or(BalanceError::CoefficientOverflow)?;
+        }
+        let pivot_row = matrix[row].clone();
+        for (other, values) in matrix.iter_mut().enumerate() {
+            let factor = values[column];
+            if other != row && !factor.is_zero() {
+                for (value, pivot_value) in values.iter_mut().zip(&pivot_row) {
+                    *value = factor
+                        .checked_mul(*pivot_value)
+                        .and_then(|product| value.checked_sub(product))
+                        .ok_or(BalanceError::CoefficientOverflow)?;
+                }
+            }
+        }
+
+        pivots.push(column);
+        row += 1;
+    }
+
+    let free: Vec<usize> = (0..columns).filter(|c| !pivots.contains(c)).collect();
+    let free_column = match free.len() {
+        0 => return Err(BalanceError::Impossible),
+        1 => free[0],
+        count => return Err(BalanceError::MultipleSolutions(count)),
+    };
+
+    let mut solution = vec![Ratio::from(0); columns];
+    solution[free_column] = Ratio::from(1);
+    for (row, &column) in pivots.iter().enumerate() {
+        solution[column] = Ratio::from(0)
+            .checked_sub(matrix[row][free_column])
+            .ok_or(BalanceError::CoefficientOverflow)?;
+    }
+
+    let denominators = solution
+        .iter()
+        .try_fold(1, |acc, value| lcm(acc, value.den))
+        .ok_or(BalanceError::CoefficientOverflow)?;
+    let integers = solution
+        .iter()
+        .map(|value| value.num.checked_mul(denominators / value.den))
+        .collect::<Option<Vec<i128>>>()
+        .ok_or(BalanceError::CoefficientOverflow)?;
+    let divisor = integers
+        .iter()
+        .try_fold(0, |acc, value| gcd(acc, *value))
+        .ok_or(BalanceError::CoefficientOverflow)?;
+    let sign = if integers.iter().any(|value| *value < 0) {
+        -1
+    } else {
+        1
+    };
+
+    integers
+        .iter()
+        .map(|value| {
+            let value = value / divisor * sign;
+            if value <= 0 {
+                Err(BalanceError::Impossible)
+            } else {
+                u16::try_from(value).map_err(|_| BalanceError::CoefficientOverflow)
+            }
+        })
+        .collect()
+}
+
+/// None only when the result does not fit in an i128, i.e. for i128::MIN
+fn gcd(a: i128, b: i128) -> Option<i128> {
+    if b == 0 {
+        a.checked_abs()
+    } else {
+        gcd(b, a.checked_rem(b)?)
+    }
+}
+
+fn lcm(a: i128, b: i128) -> Option<i128> {
+    (a / gcd(a, b)?).checked_mul(b)
+}
+
+/// An exact fraction, always stored in lowest terms with a positive denominator
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Ratio {
+    num: i128,
+    den: i128,
+}
+
+/// The arithmetic is checked, returning None on overflow,
+/// as large subscripts in many species can outgrow even an i128
+impl Ratio {
+    fn new(num: i128, den: i128) -> Option<Self> {
+        let divisor = gcd(num, den)?.checked_mul(den.signum())?;
+        Some(Self {
+            num: num.checked_div(divisor)?,
+            den: den.checked_div(divisor)?,
+        })
+    }
+
+    fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.num
+                .checked_mul(other.den)?
+                .checked_sub(other.num.checked_mul(self.den)?)?,
+            self.den.checked_mul(other.den)?,
+        )
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.num.checked_mul(other.num)?,
+            self.den.checked_mul(other.den)?,
+        )
+    }
+
+    fn checked_div(self, other: Self) -> Option<Self> {
+        Self::new(
+            self.num.checked_mul(other.den)?,
+            self.den.checked_mul(other.num)?,
+        )
+    }
+}
+
+impl From<i128> for Ratio {
+    fn from(value: i128) -> Self {
+        Self { num: value, den: 1 }
+    }
+}
+
+impl Node {
+    /// See `balance::balance`
+    pub fn balance(&self) -> Result<Node, BalanceError> {
+        balance(self)
+    }
+
+    /// See `balance::report`
+    pub fn balance_report(&self) -> Result<BalanceReport, BalanceError> {
+        report(self)
+    }
+
+    /// Whether every element has the same count, and the net charge is the same,
+    /// on both sides of the equation
+    pub fn is_balanced(&self) -> Result<bool, BalanceError> {
+        report(self).map(|report| report.is_balanced())
+    }
+}
+
+impl Equation {
+    /// See `balance::balance`
+    pub fn balance(&self) -> Result<Equation, BalanceError> {
+        balance_equation(self)
+    }
+
+    /// See `balance::report`
+    pub fn balance_report(&self) -> Result<BalanceReport, BalanceError> {
+        equation_report(self)
+    }
+
+    /// Whether every element has the same count, and the net charge is the same,
+    /// on both sides of the equation
+    pub fn is_balanced(&self) -> Result<bool, BalanceError> {
+        self.balance_report().map(|report| report.is_balanced())
+    }
+
+    /// See `balance::combine`
+    pub fn combine(&self, other: &Equation) -> Result<Equation, BalanceError> {
+        combine_equations(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_equation};
+
+    fn coefficients(node: &Node) -> Vec<u16> {
+        let Node::Equation(equation) = node else {
+            panic!("Expected an equation");
+        };
+        equation
+            .reactants()
+            .units()
+            .iter()
+            .chain(equation.products().units())
+            .map(FormulaUnit::coefficient)
+            .collect()
+    }
+
+    #[test]
+    fn can_balance_rusting() {
+        let ast = parse(String::from("Fe+O2->Fe2O3")).unwrap();
+        let balanced = ast.balance().unwrap();
+
+        assert_eq!(vec![4, 3, 2], coefficients(&balanced));
+        assert_eq!(parse(String::from("4Fe+3O2->2Fe2O3")).unwrap(), balanced);
+    }
+
+    #[test]
+    fn can_balance_typed_equations() {
+        let equation = parse_equation(String::from("Al+Cl2->AlCl3")).unwrap();
+        let balanced = equation.balance().unwrap();
+
+        assert_eq!(
+            parse_equation(String::from("2Al+3Cl2->2AlCl3")),
+            Ok(balanced)
+        );
+        assert_eq!(Ok(false), equation.is_balanced());
+    }
+
+    #[test]
+    fn can_balance_half_reactions() {
+        let ast = parse(String::from("MnO4^- + H^+ + e- -> Mn^2+ + H2O")).unwrap();
+        assert_eq!(vec![1, 8, 5, 1, 4], coefficients(&ast.balance().unwrap()));
+
+        let ast = parse(String::from("Cu^2+ + 2e- -> Cu")).unwrap();
+        assert_eq!(Ok(true), ast.is_balanced());
+        let ast = parse(String::from("Cu^2+ + e- -> Cu")).unwrap();
+        assert_eq!(Ok(false), ast.is_balanced());
+    }
+
+    #[test]
+    fn can_combine_half_reactions() {
+        let reduction = parse(String::from("MnO4^- + H^+ + e- -> Mn^2+ + H2O")).unwrap();
+        let oxidation = parse(String::from("Fe^2+ -> Fe^3+ + e-")).unwrap();
+        let combined = combine(&reduction, &oxidation).unwrap();
+        assert_eq!(
+            parse(String::from(
+                "MnO4^- + 8H^+ + 5Fe^2+ -> Mn^2+ + 4H2O + 5Fe^3+"
+            )),
+            Ok(combined.clone())
+        );
+        assert_eq!(Ok(true), combined.is_balanced());
+
+        // water on both sides cancels out
+        let reduction = parse_equation(String::from("O2 + H2O + e- -> OH^-")).unwrap();
+        let oxidation = parse_equation(String::from("Zn + OH^- -> Zn(OH)2 + e-")).unwrap();
+        assert_eq!(
+            parse_equation(String::from("O2 + 2H2O + 2Zn -> 2Zn(OH)2")),
+            Ok(reduction.combine(&oxidation).unwrap())
+        );
+    }
+
+    #[test]
+    fn rejects_half_reactions_in_the_same_direction() {
+        let first = parse(String::from("Cu^2+ + e- -> Cu")).unwrap();
+        let second = parse(String::from("Fe^3+ + e- -> Fe^2+")).unwrap();
+        assert_eq!(
+            Err(BalanceError::ElectronsDoNotCancel),
+            combine(&first, &second)
+        );
+
+        let full = parse(String::from("H2 + O2 -> H2O")).unwrap();
+        assert_eq!(
+            Err(BalanceError::ElectronsDoNotCancel),
+            combine(&first, &full)
+        );
+        let formula = parse(String::from("H2O")).unwrap();
+        assert_eq!(Err(BalanceError::NotAnEquation), combine(&first, &formula));
+    }
+
+    #[test]
+    fn reduces_combined_coefficients() {
+        // chlorine disproportionating, where every coefficient is even after cancelling
+        let reduction = parse(String::from("Cl2 + e- -> Cl^-")).unwrap();
+        let oxidation = parse(String::from("Cl2 + H2O -> ClO3^- + H^+ + e-")).unwrap();
+        assert_eq!(
+            parse(String::from("3Cl2 + 3H2O -> 5Cl^- + ClO3^- + 6H^+")),
+            Ok(combine(&reduction, &oxidation).unwrap())
+        );
+    }
+
+    #[test]
+    fn keeps_the_arrow_of_half_reactions() {
+        let reduction = parse(String::from("Fe^3+ + e- -[aq]-> Fe^2+")).unwrap();
+        let oxidation = parse(String::from("Cu <=[aq]=> Cu^2+ + e-")).unwrap();
+        let combined = combine(&reduction, &oxidation);
+        assert_eq!(Err(BalanceError::MismatchedArrows), combined);
+
+        let reduction = parse(String::from("Fe^3+ + e- <=[aq]=> Fe^2+")).unwrap();
+        let combined = combine(&reduction, &oxidation).unwrap();
+        assert_eq!(
+            parse(String::from("2Fe^3+ + Cu <=[aq]=> 2Fe^2+ + Cu^2+")),
+            Ok(combined)
+        );
+    }
+
+    #[test]
+    fn rejects_half_reactions_which_cancel_out() {
+        let oxidation = parse(String::from("Fe^2+ -> Fe^3+ + e-")).unwrap();
+        let reduction = parse(String::from("Fe^3+ + e- -> Fe^2+")).unwrap();
+        assert_eq!(
+            Err(BalanceError::SideCancelled),
+            combine(&oxidation, &reduction)
+        );
+    }
+
+    #[test]
+    fn keeps_the_arrow() {
+        let equation = parse_equation(String::from("N2+H2<=[Fe]=>NH3")).unwrap();
+        assert_eq!(
+            parse_equation(String::from("N2+3H2<=[Fe]=>2NH3")),
+            Ok(equation.balance().unwrap())
+        );
+    }
+
+    #[test]
+    fn can_balance_combustion() {
+        let ast = parse(String::from("C3H8+O2->CO2+H2O")).unwrap();
+        assert_eq!(vec![1, 5, 3, 4], coefficients(&ast.balance().unwrap()));
+    }
+
+    #[test]
+    fn can_balance_groups() {
+        let ast = parse(String::from("Ca(OH)2+HNO3->Ca(NO3)2+H2O")).unwrap();
+        assert_eq!(vec![1, 2, 1, 2], coefficients(&ast.balance().unwrap()));
+    }
+
+    #[test]
+    fn keeps_hydrate_water() {
+        let ast = parse(String::from("CuSO4·5H2O->CuSO4+H2O")).unwrap();
+        let balanced = ast.balance().unwrap();
+
+        assert_eq!(vec![1, 1, 5], coefficients(&balanced));
+        assert!(balanced.is_balanced().unwrap());
+    }
+
+    #[test]
+    fn ignores_existing_coefficients() {
+        let ast = parse(String::from("7H2+9O2->3H2O")).unwrap();
+        assert_eq!(vec![2, 1, 2], coefficients(&ast.balance().unwrap()));
+    }
+
+    #[test]
+    fn conserves_charge() {
+        let ast = parse(String::from("Cu+Ag^+->Cu^2++Ag")).unwrap();
+        let balanced = ast.balance().unwrap();
+
+        assert_eq!(vec![1, 2, 1, 2], coefficients(&balanced));
+        assert_eq!(
+            parse(String::from("Cu+2Ag^+->Cu^2++2Ag")).unwrap(),
+            balanced
+        );
+    }
+
+    #[test]
+    fn reports_impossible_equations() {
+        let ast = parse(String::from("H2+He->O2")).unwrap();
+        assert_eq!(Err(BalanceError::Impossible), ast.balance());
+
+        // only balances if H2O and O2 have a coefficient of 0
+        let ast = parse(String::from("NaCl+H2O->NaCl+O2")).unwrap();
+        assert_eq!(Err(BalanceError::Impossible), ast.balance());
+    }
+
+    #[test]
+    fn reports_multiple_solutions() {
+        let ast = parse(String::from("H2+O2->H2O+H2O2")).unwrap();
+        assert_eq!(Err(BalanceError::MultipleSolutions(2)), ast.balance());
+    }
+
+    #[test]
+    fn can_check_balanced_equations() {
+        let ast = parse(String::from("4Fe+3O2->2Fe2O3")).unwrap();
+        assert_eq!(Ok(true), ast.is_balanced());
+        assert_eq!(Ok(true), ast.balance().unwrap().is_balanced());
+
+        let ast = parse(String::from("Fe+O2->Fe2O3")).unwrap();
+        assert_eq!(Ok(false), ast.is_balanced());
+    }
+
+    #[test]
+    fn reports_each_element() {
+        let ast = parse(String::from("CH4+O2->CO2+2H2O")).unwrap();
+        let report = ast.balance_report().unwrap();
+
+        let exp = vec![
+            ElementBalance {
+                symbol: "C".to_owned(),
+                reactants: 1,
+                products: 1,
+            },
+            ElementBalance {
+                symbol: "H".to_owned(),
+                reactants: 4,
+                products: 4,
+            },
+            ElementBalance {
+                symbol: "O".to_owned(),
+                reactants: 2,
+                products: 4,
+            },
+        ];
+        assert_eq!(exp, report.elements);
+
+        let unbalanced: Vec<_> = report.unbalanced().collect();
+        assert_eq!(1, unbalanced.len());
+        assert_eq!("O", unbalanced[0].symbol);
+        assert_eq!(2, unbalanced[0].difference());
+    }
+
+    #[test]
+    fn reports_charge() {
+        let ast = parse(String::from("Cu+Ag^+->Cu^2++Ag")).unwrap();
+        let report = ast.balance_report().unwrap();
+
+        assert_eq!(0, report.unbalanced().count());
+        assert_eq!(1, report.reactants_charge);
+        assert_eq!(2, report.products_charge);
+        assert!(!report.is_balanced());
+    }
+
+    #[test]
+    fn reports_elements_missing_from_one_side() {
+        let ast = parse(String::from("H2+Cl2->2HCl+Na")).unwrap();
+        let report = ast.balance_report().unwrap();
+        let unbalanced: Vec<_> = report.unbalanced().collect();
+
+        assert_eq!(1, unbalanced.len());
+        assert_eq!("Na", unbalanced[0].symbol);
+        assert_eq!(0, unbalanced[0].reactants);
+        assert_eq!(1, unbalanced[0].difference());
+    }
+
+    #[test]
+    fn rejects_formula_units() {
+        let ast = parse(String::from("H2O")).unwrap();
+        assert_eq!(Err(BalanceError::NotAnEquation), ast.balance());
+        assert_eq!(Err(BalanceError::NotAnEquation), ast.is_balanced());
+    }
+
+    #[test]
+    fn reports_overflow_in_elimination() {
+        let ast = parse(String::from(
+            "A65535B65534C65533D65531E65529+A65521B65519C65497+D65479E65449+A65447B65437+C65423E65419\
+             ->A65413B65407C65393+D65381E65371+A65357C65353+B65327D65323E65309",
+        ))
+        .unwrap();
+        assert_eq!(Err(BalanceError::CoefficientOverflow), ast.balance());
+    }
+
+    #[test]
+    fn reports_charge_overflow() {
+        let ast = parse(String::from("((H^-)65535)65535->H")).unwrap();
+        let overflow = || BalanceError::Composition(CompositionError::Overflow);
+        assert_eq!(Err(overflow()), ast.balance());
+        assert_eq!(Err(overflow()), ast.balance_report());
+    }
+}
@@ -0,0 +1,283 @@
+use crate::ast_types::{FormulaUnit, Node, Side, Species, Term};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// A map of element symbol to atom count, ordered by symbol
+pub type Composition = BTreeMap<String, u32>;
+
+/// The reasons the atoms of a node can not be counted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompositionError {
+    /// The node is a `Node::Equation`, which has no single composition or charge
+    NotAFormula,
+    /// An atom count does not fit in a u32, or a charge in an i32
+    Overflow,
+}
+
+impl Display for CompositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAFormula => write!(f, "An equation has no single composition or charge"),
+            Self::Overflow => write!(f, "Atom count or charge overflowed"),
+        }
+    }
+}
+
+impl Error for CompositionError {}
+
+/// Count the atoms of each element in a node.
+/// Group subscripts and formula unit coefficients are multiplied through,
+/// so 2(NH4)2SO4 has 4 N, 16 H, 2 S and 8 O.
+/// The parts of an adduct are added together, so CuSO4·5H2O has 10 H.
+/// A whole equation has no single composition, use `Side::composition` for each side
+pub fn composition(node: &Node) -> Result<Composition, CompositionError> {
+    match node {
+        Node::Formula(unit) => unit.composition(),
+        Node::Equation(_) => Err(CompositionError::NotAFormula),
+    }
+}
+
+/// Add the atoms of a formula unit, multiplied by `multiplier` instead of its coefficient, into `map`
+pub(crate) fn accumulate(
+    unit: &FormulaUnit,
+    multiplier: u32,
+    map: &mut Composition,
+) -> Result<(), CompositionError> {
+    // electrons have no parts, so no atoms
+    for part in unit.parts() {
+        accumulate_terms(part.terms(), multiply(part.count(), multiplier)?, map)?;
+    }
+    Ok(())
+}
+
+fn accumulate_terms(
+    terms: &[Term],
+    multiplier: u32,
+    map: &mut Composition,
+) -> Result<(), CompositionError> {
+    for term in terms {
+        match term {
+            Term::Element(element) => {
+                let total = map.entry(element.symbol().to_owned()).or_insert(0);
+                *total = multiply(element.count(), multiplier)?
+                    .checked_add(*total)
+                    .ok_or(CompositionError::Overflow)?;
+            }
+            Term::Group(group) => {
+                accumulate_terms(group.terms(), multiply(group.subscript(), multiplier)?, map)?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Calculate the net charge of a node, e.g. -2 for SO4^2- and -4 for 2SO4^2-.
+/// Charges written inside a group are multiplied by its subscript, and each electron is -1
+pub fn charge(node: &Node) -> Result<i32, CompositionError> {
+    match node {
+        Node::Formula(unit) => unit.net_charge(),
+        Node::Equation(_) => Err(CompositionError::NotAFormula),
+    }
+}
+
+/// The charge of one formula unit, ignoring its coefficient
+pub(crate) fn unit_charge(unit: &FormulaUnit) -> Result<i32, CompositionError> {
+    match unit.species() {
+        Species::Formula(parts) => parts.iter().try_fold(0, |total, part| {
+            let charge = scale(terms_charge(part.terms())?, part.charge(), part.count())?;
+            add(total, charge)
+        }),
+        Species::Electron(_) => Ok(-1),
+    }
+}
+
+fn terms_charge(terms: &[Term]) -> Result<i32, CompositionError> {
+    terms.iter().try_fold(0, |total, term| {
+        let charge = match term {
+            Term::Element(_) => 0,
+            Term::Group(group) => scale(
+                terms_charge(group.terms())?,
+                group.charge(),
+                group.subscript(),
+            )?,
+        };
+        add(total, charge)
+    })
+}
+
+/// (terms + charge) * count, the charge of a bracketed group or adduct part
+fn scale(terms: i32, charge: i16, count: u16) -> Result<i32, CompositionError> {
+    add(terms, charge as i32)?
+        .checked_mul(count as i32)
+        .ok_or(CompositionError::Overflow)
+}
+
+fn add(a: i32, b: i32) -> Result<i32, CompositionError> {
+    a.checked_add(b).ok_or(CompositionError::Overflow)
+}
+
+fn multiply(count: u16, multiplier: u32) -> Result<u32, CompositionError> {
+    (count as u32)
+        .checked_mul(multiplier)
+        .ok_or(CompositionError::Overflow)
+}
+
+impl Node {
+    /// See `composition::composition`
+    pub fn composition(&self) -> Result<Composition, CompositionError> {
+        composition(self)
+    }
+
+    /// See `composition::charge`
+    pub fn charge(&self) -> Result<i32, CompositionError> {
+        charge(self)
+    }
+}
+
+impl FormulaUnit {
+    /// See `composition::composition`
+    pub fn composition(&self) -> Result<Composition, CompositionError> {
+        let mut map = Composition::new();
+        accumulate(self, self.coefficient() as u32, &mut map)?;
+        Ok(map)
+    }
+
+    /// The charge of the formula unit times its coefficient, e.g. -4 for 2SO4^2-
+    pub fn net_charge(&self) -> Result<i32, CompositionError> {
+        unit_charge(self)?
+            .checked_mul(self.coefficient() as i32)
+            .ok_or(CompositionError::Overflow)
+    }
+}
+
+impl Side {
+    /// The sum of the compositions of every formula unit
+    pub fn composition(&self) -> Result<Composition, CompositionError> {
+        let mut map = Composition::new();
+        for unit in self.units() {
+            accumulate(unit, unit.coefficient() as u32, &mut map)?;
+        }
+        Ok(map)
+    }
+
+    /// The sum of the net charges of every formula unit
+    pub fn net_charge(&self) -> Result<i32, CompositionError> {
+        self.units()
+            .iter()
+            .try_fold(0, |total, unit| add(total, unit.net_charge()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn expected(pairs: &[(&str, u32)]) -> Composition {
+        pairs
+            .iter()
+            .map(|(symbol, count)| (symbol.to_string(), *count))
+            .collect()
+    }
+
+    #[test]
+    fn can_count_formula_unit() {
+        let ast = parse(String::from("2Fe2O3")).unwrap();
+        assert_eq!(Ok(expected(&[("Fe", 4), ("O", 6)])), ast.composition());
+    }
+
+    #[test]
+    fn merges_repeated_elements() {
+        let ast = parse(String::from("CH3COOH")).unwrap();
+        assert_eq!(
+            Ok(expected(&[("C", 2), ("H", 4), ("O", 2)])),
+            ast.composition()
+        );
+    }
+
+    #[test]
+    fn multiplies_nested_groups() {
+        let ast = parse(String::from("2(NH4)2SO4")).unwrap();
+        assert_eq!(
+            Ok(expected(&[("H", 16), ("N", 4), ("O", 8), ("S", 2)])),
+            ast.composition()
+        );
+
+        let ast = parse(String::from("K4(Fe(CN)6)")).unwrap();
+        assert_eq!(
+            Ok(expected(&[("C", 6), ("Fe", 1), ("K", 4), ("N", 6)])),
+            ast.composition()
+        );
+    }
+
+    #[test]
+    fn adds_adduct_parts() {
+        let ast = parse(String::from("2CuSO4·5H2O")).unwrap();
+        assert_eq!(
+            Ok(expected(&[("Cu", 2), ("H", 20), ("O", 18), ("S", 2)])),
+            ast.composition()
+        );
+    }
+
+    #[test]
+    fn can_calculate_charge() {
+        let ast = parse(String::from("2SO4^2-")).unwrap();
+        assert_eq!(Ok(-4), ast.charge());
+
+        let ast = parse(String::from("NH4+")).unwrap();
+        assert_eq!(Ok(1), ast.charge());
+
+        // a made up ion to check group charges are multiplied
+        let ast = parse(String::from("Ca(NO3^-)2^+")).unwrap();
+        assert_eq!(Ok(-1), ast.charge());
+    }
+
+    #[test]
+    fn electrons_are_charge_without_atoms() {
+        let ast = parse(String::from("2e-")).unwrap();
+        assert_eq!(Ok(-2), ast.charge());
+        assert_eq!(Ok(Composition::new()), ast.composition());
+
+        let ast = parse(String::from("Fe^3+ + e- -> Fe^2+")).unwrap();
+        let Node::Equation(equation) = ast else {
+            panic!("Expected an equation");
+        };
+        assert_eq!(Ok(2), equation.reactants().net_charge());
+        assert_eq!(Ok(2), equation.products().net_charge());
+    }
+
+    #[test]
+    fn sums_equation_sides() {
+        let ast = parse(String::from("4Fe+3O2->2Fe2O3")).unwrap();
+        if let Node::Equation(equation) = ast {
+            let (reactants, products) = (equation.reactants(), equation.products());
+            assert_eq!(reactants.composition(), products.composition());
+            assert_eq!(
+                Ok(expected(&[("Fe", 4), ("O", 6)])),
+                reactants.composition()
+            );
+            assert_eq!(Ok(0), reactants.net_charge());
+        } else {
+            panic!("Expected an equation");
+        }
+    }
+
+    #[test]
+    fn reports_typed_errors() {
+        let ast = parse(String::from("4Fe+3O2->2Fe2O3")).unwrap();
+        assert_eq!(Err(CompositionError::NotAFormula), ast.composition());
+        assert_eq!(Err(CompositionError::NotAFormula), ast.charge());
+
+        let ast = parse(String::from("((H65535)65535)65535")).unwrap();
+        assert_eq!(Err(CompositionError::Overflow), ast.composition());
+
+        let ast = parse(String::from("((((NO3^-)65535)65535)65535)")).unwrap();
+        assert_eq!(Err(CompositionError::Overflow), ast.charge());
+
+        let ast = parse(String::from("65535H·65535H^30000-")).unwrap();
+        assert_eq!(Err(CompositionError::Overflow), ast.charge());
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    error::{ParseError, Span},
+    periodic_table::{self, ELEMENTS},
+};
+
+/// A message about a span of the source, which can be rendered
+/// with the offending line and a caret underneath it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub span: Span,
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            hint: None,
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Describe a parse error, with a hint if one can be guessed from the source it came from
+    pub fn from_error(error: &ParseError, source: &str) -> Self {
+        let diagnostic = Self::new(error.message(), error.span());
+        match hint(error, source) {
+            Some(hint) => diagnostic.with_hint(hint),
+            None => diagnostic,
+        }
+    }
+
+    /// Render the diagnostic against the source it is about, e.g.
+    /// ```text
+    /// error: Unknown element: L
+    ///  --> 1:2
+    ///   |
+    /// 1 | CL2
+    ///   |  ^ did you mean `Cl`?
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let start = self.span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |index| start + index);
+        let (line, column) = self.span.line_column(source);
+
+        let end = self.span.end.clamp(start, line_end);
+        let width = source[start..end].chars().count().max(1);
+
+        let number = line.to_string();
+        let gutter = " ".repeat(number.len());
+        let mut underline = format!("{}{}", " ".repeat(column - 1), "^".repeat(width));
+        if let Some(hint) = &self.hint {
+            underline.push(' ');
+            underline.push_str(hint);
+        }
+
+        format!(
+            "error: {}\n{gutter}--> {line}:{column}\n{gutter} |\n{number} | {}\n{gutter} | {underline}\n",
+            self.message,
+            &source[line_start..line_end],
+        )
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        Self::new(error.message(), error.span())
+    }
+}
+
+fn hint(error: &ParseError, source: &str) -> Option<String> {
+    match error {
+        // CL is C followed by an unknown L, Cl was probably meant
+        ParseError::UnknownElement { symbol, span } => {
+            let previous = source.get(..span.start)?.chars().last();
+            let joined = previous
+                .filter(|val| val.is_ascii_uppercase())
+                .map(|val| format!("{}{}", val, symbol.to_lowercase()));
+            match joined {
+                Some(joined) if periodic_table::lookup(&joined).is_some() => {
+                    Some(format!("did you mean `{}`?", joined))
+                }
+                _ => similar_symbols(symbol),
+            }
+        }
+        // co is cobalt with a lowercase first letter
+        ParseError::UnknownCharacter { character, span } if character.is_ascii_lowercase() => {
+            let mut candidate = character.to_ascii_uppercase().to_string();
+            if let Some(next @ 'a'..='z') = source.get(span.end..)?.chars().next() {
+                candidate.push(next);
+            }
+            if periodic_table::lookup(&candidate).is_none() {
+                candidate.truncate(1);
+            }
+            match periodic_table::lookup(&candidate) {
+                Some(_) => Some(format!("did you mean `{}`?", candidate)),
+                None if *character == 'e' => Some("electrons are written `e-`".to_owned()),
+                None => None,
+            }
+        }
+        ParseError::UnbalancedParen { span } => match source.get(span.start..span.end)? {
+            "(" => Some("missing `)`".to_owned()),
+            "[" => Some("missing `]`".to_owned()),
+            "{" => Some("missing `}`".to_owned()),
+            ")" => Some("missing `(`".to_owned()),
+            "]" => Some("missing `[`".to_owned()),
+            _ => Some("missing `{`".to_owned()),
+        },
+        ParseError::MismatchedBracket { expected, .. } => {
+            Some(format!("replace with `{}`", expected))
+        }
+        ParseError::UnterminatedYields { span } => match source.get(span.start..span.end)? {
+            "<=" => Some("did you mean `<=>`?".to_owned()),
+            _ => Some("did you mean `->`?".to_owned()),
+        },
+        ParseError::MissingChargeSign { .. } => {
+            Some("add a `+` or `-` after the charge e.g. `^2+`".to_owned())
+        }
+        ParseError::UnexpectedWhitespace { .. } => Some("remove the whitespace".to_owned()),
+        ParseError::ElementTooLong { .. } => {
+            Some("element symbols have one or two letters".to_owned())
+        }
+        ParseError::NumberOverflow { .. } => Some(format!("numbers can be at most {}", u16::MAX)),
+        ParseError::UnexpectedEnd { expected, .. } => Some(format!("expected {}", expected)),
+        _ => None,
+    }
+}
+
+/// Suggest known symbols starting with the same letter as an unknown one
+fn similar_symbols(symbol: &str) -> Option<String> {
+    let first = symbol.chars().next()?;
+    let similar: Vec<String> = ELEMENTS
+        .iter()
+        .filter(|element| element.symbol.starts_with(first))
+        .map(|element| format!("`{}`", element.symbol))
+        .collect();
+    match similar.len() {
+        0 => None,
+        1 => Some(format!("did you mean {}?", similar[0])),
+        _ => Some(format!("did you mean one of {}?", similar.join(", "))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, parse_validated};
+
+    fn render(source: &str) -> String {
+        let error = parse_validated(source.to_owned()).unwrap_err();
+        Diagnostic::from_error(&error, source).render(source)
+    }
+
+    #[test]
+    fn renders_caret_under_token() {
+        let exp = "\
+error: Unknown element: L
+ --> 1:2
+  |
+1 | CL2
+  |  ^ did you mean `Cl`?
+";
+        assert_eq!(exp, render("CL2"));
+    }
+
+    #[test]
+    fn underlines_whole_token() {
+        let exp = "\
+error: Unknown element: Fx
+ --> 1:6
+  |
+1 | 2Fe +Fx
+  |      ^^ did you mean one of `F`, `Fe`, `Fr`, `Fm`, `Fl`?
+";
+        assert_eq!(exp, render("2Fe +Fx"));
+    }
+
+    #[test]
+    fn renders_the_offending_line() {
+        let source = "2H2 + O2\n-> 2H2O$";
+        let error = parse(source.to_owned()).unwrap_err();
+        let exp = "\
+error: Invalid Character: $
+ --> 2:8
+  |
+2 | -> 2H2O$
+  |        ^
+";
+        assert_eq!(exp, Diagnostic::from(&error).render(source));
+    }
+
+    #[test]
+    fn suggests_capitalising_elements() {
+        assert!(render("co2").ends_with("^ did you mean `Co`?\n"));
+        assert!(render("o2").ends_with("^ did you mean `O`?\n"));
+    }
+
+    #[test]
+    fn suggests_missing_parens() {
+        let exp = "\
+error: Unbalanced paren
+ --> 1:3
+  |
+1 | Ca)OH
+  |   ^ missing `(`
+";
+        assert_eq!(exp, render("Ca)OH"));
+    }
+
+    #[test]
+    fn says_what_is_missing_at_the_end() {
+        assert!(render("H2+O2").ends_with("^ expected an arrow\n"));
+        assert!(render("H2+O2->").ends_with("^ expected a formula unit\n"));
+        assert!(render("H2 +").ends_with("^ expected a formula unit\n"));
+    }
+
+    #[test]
+    fn suggests_closing_parens() {
+        let exp = "\
+error: Unbalanced paren
+ --> 1:3
+  |
+1 | Ca(OH2
+  |   ^ missing `)`
+";
+        assert_eq!(exp, render("Ca(OH2"));
+    }
+
+    #[test]
+    fn suggests_matching_brackets() {
+        let exp = "\
+error: Mismatched bracket, expected ]
+ --> 1:10
+  |
+1 | [Cu(NH3)4)SO4
+  |          ^ replace with `]`
+";
+        assert_eq!(exp, render("[Cu(NH3)4)SO4"));
+        assert!(render("K3[Fe(CN)6").ends_with("^ missing `]`\n"));
+    }
+
+    #[test]
+    fn suggests_finishing_arrows() {
+        assert!(render("N2 -N").ends_with("^ did you mean `->`?\n"));
+        assert!(render("N2 <= N").ends_with("^^ did you mean `<=>`?\n"));
+    }
+
+    #[test]
+    fn suggests_writing_electrons() {
+        assert!(render("Cu^2+ + 2e -> Cu").ends_with("^ electrons are written `e-`\n"));
+    }
+}
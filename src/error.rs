@@ -0,0 +1,133 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// A range of bytes in the parsed string, `start` inclusive and `end` exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The line and column of the start of the span in `source`, both counted from 1.
+    /// Columns count characters, not bytes
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let start = self.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+        let line = source[..start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count() + 1;
+        (line, column)
+    }
+
+    /// The smallest span covering both spans
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// An error from lexing or parsing, with the span of the offending characters
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// A character which can not start a token e.g. `$`
+    UnknownCharacter { character: char, span: Span },
+    /// A `-` which is not part of `->` or a trailing charge
+    UnterminatedYields { span: Span },
+    /// A charge which has no sign e.g. `^2`
+    MissingChargeSign { span: Span },
+    /// A number which does not fit in a u16
+    NumberOverflow { span: Span },
+    /// Whitespace between a number and what it is the subscript of e.g. `H 2`
+    UnexpectedWhitespace { span: Span },
+    /// An element symbol with more than two letters e.g. `Abc`
+    ElementTooLong { span: Span },
+    /// An element symbol which is not in the periodic table
+    UnknownElement { symbol: String, span: Span },
+    /// A closing paren with no opening paren, or an opening paren which is never closed
+    UnbalancedParen { span: Span },
+    /// A group closed with a different kind of bracket than it was opened with e.g. `(OH]`
+    MismatchedBracket { expected: char, span: Span },
+    /// A token which is not allowed where it appears
+    UnexpectedToken { found: String, span: Span },
+    /// The input ended where more was expected e.g. a formula unit after `->`
+    UnexpectedEnd { expected: String, span: Span },
+}
+
+impl ParseError {
+    /// The span of the offending characters
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnknownCharacter { span, .. }
+            | Self::UnterminatedYields { span }
+            | Self::MissingChargeSign { span }
+            | Self::NumberOverflow { span }
+            | Self::UnexpectedWhitespace { span }
+            | Self::ElementTooLong { span }
+            | Self::UnknownElement { span, .. }
+            | Self::UnbalancedParen { span }
+            | Self::MismatchedBracket { span, .. }
+            | Self::UnexpectedToken { span, .. }
+            | Self::UnexpectedEnd { span, .. } => *span,
+        }
+    }
+
+    pub(crate) fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Self::UnknownCharacter { span, .. }
+            | Self::UnterminatedYields { span }
+            | Self::MissingChargeSign { span }
+            | Self::NumberOverflow { span }
+            | Self::UnexpectedWhitespace { span }
+            | Self::ElementTooLong { span }
+            | Self::UnknownElement { span, .. }
+            | Self::UnbalancedParen { span }
+            | Self::MismatchedBracket { span, .. }
+            | Self::UnexpectedToken { span, .. }
+            | Self::UnexpectedEnd { span, .. } => span,
+        }
+    }
+
+    /// A description of the error, without its location
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnknownCharacter { character, .. } => {
+                format!("Invalid Character: {}", character)
+            }
+            Self::UnterminatedYields { .. } => "Yield sign (->) unfinished".to_owned(),
+            Self::MissingChargeSign { .. } => "Charge (^) missing sign".to_owned(),
+            Self::NumberOverflow { .. } => "Number is too large".to_owned(),
+            Self::UnexpectedWhitespace { .. } => "Unexpected whitespace before number".to_owned(),
+            Self::ElementTooLong { .. } => {
+                "Formula should not have 3 letter element names".to_owned()
+            }
+            Self::UnknownElement { symbol, .. } => format!("Unknown element: {}", symbol),
+            Self::UnbalancedParen { .. } => "Unbalanced paren".to_owned(),
+            Self::MismatchedBracket { expected, .. } => {
+                format!("Mismatched bracket, expected {}", expected)
+            }
+            Self::UnexpectedToken { found, .. } => format!("Unexpected token: {}", found),
+            Self::UnexpectedEnd { .. } => "Unexpected end of input".to_owned(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.message(), self.span().start)
+    }
+}
+
+impl Error for ParseError {}
@@ -1,24 +1,39 @@
-use crate::token_types::{ParenType, TokenMetadata, Tokens};
+use crate::{
+    ast_types::{Arrow, BracketKind, State},
+    error::{ParseError, Span},
+    token_types::{ParenType, TokenMetadata, Tokens},
+    unicode::{subscript_digit, superscript_digit},
+};
 use std::{
-    iter::{Enumerate, Iterator, Peekable},
-    str::Chars,
+    collections::VecDeque,
+    iter::{Iterator, Peekable},
+    str::CharIndices,
 };
 
 /// An iterator that iterates over a string and parses it lazily
 pub struct LazyTokenStream<'a> {
-    string_iter: Peekable<Enumerate<Chars<'a>>>,
+    string_iter: Peekable<CharIndices<'a>>,
+    /// The last character of the previous token
+    previous: Option<char>,
+    /// The start of any whitespace skipped since the previous token
+    after_whitespace: Option<usize>,
+    /// Tokens already read, which come before any more of the string e.g. arrow conditions
+    pending: VecDeque<Tokens>,
 }
 
 impl<'a> LazyTokenStream<'a> {
     /// Construct a new `LazyTokenStream` from the string
-    pub fn new(string: &'a String) -> Self {
+    pub fn new(string: &'a str) -> Self {
         Self {
-            string_iter: string.chars().enumerate().peekable(),
+            string_iter: string.char_indices().peekable(),
+            previous: None,
+            after_whitespace: None,
+            pending: VecDeque::new(),
         }
     }
 }
 
-impl From<LazyTokenStream<'_>> for Result<Vec<Tokens>, String> {
+impl From<LazyTokenStream<'_>> for Result<Vec<Tokens>, ParseError> {
     fn from(other: LazyTokenStream<'_>) -> Self {
         let mut vec = Vec::new();
         for token in other {
@@ -27,65 +42,253 @@ impl From<LazyTokenStream<'_>> for Result<Vec<Tokens>, String> {
             };
             vec.push(token.unwrap());
         }
-        return Ok(vec);
+        Ok(vec)
     }
 }
 
 impl Iterator for LazyTokenStream<'_> {
-    type Item = Result<Tokens, String>;
+    type Item = Result<Tokens, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.pop_front() {
+            return Some(Ok(token));
+        }
+
+        self.after_whitespace = None;
+        while let Some((loc, val)) = self.string_iter.peek() {
+            if !val.is_whitespace() {
+                break;
+            }
+            self.after_whitespace.get_or_insert(*loc);
+            self.string_iter.next();
+        }
+
+        let token = self.next_token();
+        if let Some(Ok(token)) = &token {
+            self.previous = token.meta().raw().chars().last();
+        }
+        token
+    }
+}
+
+impl LazyTokenStream<'_> {
+    fn next_token(&mut self) -> Option<Result<Tokens, ParseError>> {
         match self.string_iter.next() {
-            // numbers
-            Some((loc, val @ '0'..='9')) => {
-                let mut temp = String::new();
-                temp.push(val);
-                loop {
-                    match self.string_iter.peek() {
-                        Some((_, inner_val @ '0'..='9')) => {
-                            temp.push(*inner_val);
-                            self.string_iter.next();
-                        }
-                        Some(_) | None => {
-                            break Some(
-                                temp.parse::<u16>()
-                                    .map_err(|e| format!("Could not parse number: {e}"))
-                                    .map(|value| Tokens::Number {
-                                        data: value,
-                                        meta: TokenMetadata::new(&temp, loc),
-                                    }),
-                            );
-                        }
-                    }
-                }
+            // a subscript can not be separated from what it follows e.g. H 2
+            Some((loc, '0'..='9'))
+                if self.after_whitespace.is_some()
+                    && matches!(
+                        self.previous,
+                        Some('A'..='Z' | 'a'..='z' | '0'..='9' | '₀'..='₉' | ')' | ']' | '}')
+                    ) =>
+            {
+                Some(Err(ParseError::UnexpectedWhitespace {
+                    span: Span::new(self.after_whitespace.unwrap(), loc),
+                }))
             }
 
-            // parens
-            Some((loc, raw @ '(')) => Some(Ok(Tokens::Paren {
-                data: ParenType::OPEN,
-                meta: TokenMetadata::new(&raw.to_string(), loc),
-            })),
-            Some((loc, raw @ ')')) => Some(Ok(Tokens::Paren {
-                data: ParenType::CLOSE,
+            // numbers, including subscript digits e.g. H₂
+            Some((loc, val @ '0'..='9')) => Some(self.number(loc, val, |digit| digit.to_digit(10))),
+            Some((loc, val @ '₀'..='₉')) => Some(self.number(loc, val, subscript_digit)),
+
+            // states e.g. (aq), otherwise parens and brackets
+            Some((loc, raw @ '(')) => match self.state_ahead(0) {
+                Some((state, length)) => {
+                    let mut temp = raw.to_string();
+                    for _ in 0..length {
+                        temp.push(self.string_iter.next().unwrap().1);
+                    }
+                    Some(Ok(Tokens::State {
+                        data: state,
+                        meta: TokenMetadata::new(&temp, loc),
+                    }))
+                }
+                None => Some(Ok(Tokens::Paren {
+                    data: ParenType::OPEN,
+                    kind: BracketKind::Round,
+                    meta: TokenMetadata::new(&raw.to_string(), loc),
+                })),
+            },
+            Some((loc, raw @ (')' | '[' | ']' | '{' | '}'))) => Some(Ok(Tokens::Paren {
+                data: match raw {
+                    '[' | '{' => ParenType::OPEN,
+                    _ => ParenType::CLOSE,
+                },
+                kind: match raw {
+                    '[' | ']' => BracketKind::Square,
+                    '{' | '}' => BracketKind::Curly,
+                    _ => BracketKind::Round,
+                },
                 meta: TokenMetadata::new(&raw.to_string(), loc),
             })),
 
-            // plus
-            Some((loc, raw @ '+')) => Some(Ok(Tokens::Plus {
+            // hydrate and adduct separators e.g. CuSO4·5H2O
+            Some((loc, raw @ ('·' | '*'))) => Some(Ok(Tokens::Separator {
                 meta: TokenMetadata::new(&raw.to_string(), loc),
             })),
 
-            // yields
-            Some((loc, '-')) => match self.string_iter.peek() {
-                Some((_, '>')) => {
+            // plus, or a trailing positive charge e.g. NH4+
+            Some((loc, raw @ '+')) => {
+                let meta = TokenMetadata::new(&raw.to_string(), loc);
+                // H2 + O2 and H2+ O2 are a plus, NH4+ + OH- starts with a charge
+                if self.after_whitespace.is_none()
+                    && self.ends_charge()
+                    && !self.unit_after_whitespace()
+                {
+                    Some(Ok(Tokens::Charge { data: 1, meta }))
+                } else {
+                    Some(Ok(Tokens::Plus { meta }))
+                }
+            }
+
+            // yields, or a trailing negative charge e.g. OH-
+            Some((loc, raw @ '-')) => {
+                if let Some((_, '[')) = self.string_iter.peek() {
+                    Some(self.conditioned_arrow(loc, "-", &[("->", Arrow::Forward)]))
+                } else if let Some((_, '>')) = self.string_iter.peek() {
                     self.string_iter.next();
                     Some(Ok(Tokens::Yields {
+                        data: Arrow::Forward,
                         meta: TokenMetadata::new("->", loc),
                     }))
+                } else if self.ends_charge() {
+                    Some(Ok(Tokens::Charge {
+                        data: -1,
+                        meta: TokenMetadata::new(&raw.to_string(), loc),
+                    }))
+                } else {
+                    Some(Err(ParseError::UnterminatedYields {
+                        span: Span::new(loc, loc + 1),
+                    }))
                 }
-                Some(_) => Some(Err("Yield sign (->) unfinished".to_owned())),
-                None => None,
-            },
+            }
+
+            // reverse, reversible and equilibrium arrows e.g. <=>, which can have conditions
+            Some((loc, '<')) => {
+                let ahead = self.ahead(2);
+                let (raw, data) = match ahead.as_str() {
+                    "-[" => {
+                        self.string_iter.next();
+                        let rights = [("->", Arrow::Reversible), ("-", Arrow::Reverse)];
+                        return Some(self.conditioned_arrow(loc, "<-", &rights));
+                    }
+                    "=[" => {
+                        self.string_iter.next();
+                        let rights = [("=>", Arrow::Equilibrium)];
+                        return Some(self.conditioned_arrow(loc, "<=", &rights));
+                    }
+                    "->" => ("<->", Arrow::Reversible),
+                    "=>" => ("<=>", Arrow::Equilibrium),
+                    _ if ahead.starts_with('-') => ("<-", Arrow::Reverse),
+                    _ if ahead.starts_with('=') => {
+                        return Some(Err(ParseError::UnterminatedYields {
+                            span: Span::new(loc, loc + 2),
+                        }))
+                    }
+                    _ => {
+                        return Some(Err(ParseError::UnknownCharacter {
+                            character: '<',
+                            span: Span::new(loc, loc + 1),
+                        }))
+                    }
+                };
+                for _ in 1..raw.len() {
+                    self.string_iter.next();
+                }
+                Some(Ok(Tokens::Yields {
+                    data,
+                    meta: TokenMetadata::new(raw, loc),
+                }))
+            }
+            Some((loc, '=')) if matches!(self.string_iter.peek(), Some((_, '['))) => {
+                Some(self.conditioned_arrow(loc, "=", &[("=", Arrow::Equals)]))
+            }
+            Some((loc, raw @ '=')) => Some(Ok(Tokens::Yields {
+                data: Arrow::Equals,
+                meta: TokenMetadata::new(&raw.to_string(), loc),
+            })),
+
+            // charge e.g. ^2-
+            Some((loc, '^')) => {
+                let mut temp = String::from("^");
+                while let Some((_, inner_val @ '0'..='9')) = self.string_iter.peek() {
+                    temp.push(*inner_val);
+                    self.string_iter.next();
+                }
+                let sign = match self.string_iter.peek() {
+                    Some((_, '+')) => 1,
+                    Some((_, '-')) => -1,
+                    _ => {
+                        return Some(Err(ParseError::MissingChargeSign {
+                            span: Span::new(loc, loc + temp.len()),
+                        }))
+                    }
+                };
+                self.string_iter.next();
+                let magnitude = match &temp[1..] {
+                    "" => Ok(1),
+                    digits => digits
+                        .parse::<i16>()
+                        .map_err(|_| ParseError::NumberOverflow {
+                            span: Span::new(loc + 1, loc + temp.len()),
+                        }),
+                };
+                temp.push(if sign > 0 { '+' } else { '-' });
+                Some(magnitude.map(|value| Tokens::Charge {
+                    data: sign * value,
+                    meta: TokenMetadata::new(&temp, loc),
+                }))
+            }
+
+            // superscript charge e.g. ²⁻
+            Some((loc, val)) if superscript_digit(val).is_some() || matches!(val, '⁺' | '⁻') => {
+                let mut raw = val.to_string();
+                let mut digits = String::new();
+                let mut last = val;
+                while let Some(digit) = superscript_digit(last) {
+                    digits.push(char::from_digit(digit, 10).unwrap());
+                    match self.string_iter.peek() {
+                        Some(&(_, next))
+                            if superscript_digit(next).is_some() || matches!(next, '⁺' | '⁻') =>
+                        {
+                            raw.push(next);
+                            last = next;
+                            self.string_iter.next();
+                        }
+                        _ => {
+                            return Some(Err(ParseError::MissingChargeSign {
+                                span: Span::new(loc, loc + raw.len()),
+                            }))
+                        }
+                    }
+                }
+                let sign = if last == '⁺' { 1 } else { -1 };
+                let magnitude = match digits.as_str() {
+                    "" => Ok(1),
+                    digits => digits
+                        .parse::<i16>()
+                        .map_err(|_| ParseError::NumberOverflow {
+                            span: Span::new(loc, loc + raw.len() - last.len_utf8()),
+                        }),
+                };
+                Some(magnitude.map(|value| Tokens::Charge {
+                    data: sign * value,
+                    meta: TokenMetadata::new(&raw, loc),
+                }))
+            }
+
+            // unicode arrows e.g. →
+            Some((loc, raw @ ('→' | '⟶' | '←' | '⇄' | '⇌'))) => {
+                Some(Ok(Tokens::Yields {
+                    data: match raw {
+                        '←' => Arrow::Reverse,
+                        '⇄' => Arrow::Reversible,
+                        '⇌' => Arrow::Equilibrium,
+                        _ => Arrow::Forward,
+                    },
+                    meta: TokenMetadata::new(&raw.to_string(), loc),
+                }))
+            }
 
             // elements
             Some((loc, val @ 'A'..='Z')) => {
@@ -95,11 +298,11 @@ impl Iterator for LazyTokenStream<'_> {
                 if let Some((_, inner_val @ 'a'..='z')) = self.string_iter.peek() {
                     temp.push(*inner_val);
                     self.string_iter.next();
-                    if let Some((_, 'a'..='z')) = self.string_iter.peek() {
+                    if let Some((end, 'a'..='z')) = self.string_iter.peek() {
                         // should not have 3 letter element names
-                        return Some(Err(
-                            "Formula should not have 3 letter element names".to_owned()
-                        ));
+                        return Some(Err(ParseError::ElementTooLong {
+                            span: Span::new(loc, end + 1),
+                        }));
                     };
                 }
 
@@ -109,10 +312,197 @@ impl Iterator for LazyTokenStream<'_> {
                 }))
             }
 
-            Some((_, c)) => Some(Err(format!("Invalid Character: {}", c))),
+            // electrons e.g. e-, e⁻ or e^-, but not e followed by an arrow
+            Some((loc, 'e')) if matches!(self.string_iter.peek(), Some((_, '-' | '⁻' | '^'))) => {
+                let raw = match self.ahead(2).as_str() {
+                    "->" | "-[" => None,
+                    ahead if ahead.starts_with('-') => Some("e-"),
+                    ahead if ahead.starts_with('⁻') => Some("e⁻"),
+                    "^-" => Some("e^-"),
+                    _ => None,
+                };
+                match raw {
+                    Some(raw) => {
+                        for _ in 1..raw.chars().count() {
+                            self.string_iter.next();
+                        }
+                        Some(Ok(Tokens::Electron {
+                            meta: TokenMetadata::new(raw, loc),
+                        }))
+                    }
+                    None => Some(Err(ParseError::UnknownCharacter {
+                        character: 'e',
+                        span: Span::new(loc, loc + 1),
+                    })),
+                }
+            }
+
+            Some((loc, c)) => Some(Err(ParseError::UnknownCharacter {
+                character: c,
+                span: Span::new(loc, loc + c.len_utf8()),
+            })),
             None => None,
         }
     }
+
+    /// Read the rest of a number after its first digit,
+    /// using `digit` to get the value of each digit character
+    fn number(
+        &mut self,
+        loc: usize,
+        first: char,
+        digit: fn(char) -> Option<u32>,
+    ) -> Result<Tokens, ParseError> {
+        let mut raw = first.to_string();
+        let mut value = digit(first).unwrap().to_string();
+        while let Some(next) = self.string_iter.peek().and_then(|(_, val)| digit(*val)) {
+            raw.push(self.string_iter.next().unwrap().1);
+            value.push(char::from_digit(next, 10).unwrap());
+        }
+        value
+            .parse::<u16>()
+            .map_err(|_| ParseError::NumberOverflow {
+                span: Span::new(loc, loc + raw.len()),
+            })
+            .map(|data| Tokens::Number {
+                data,
+                meta: TokenMetadata::new(&raw, loc),
+            })
+    }
+
+    /// The next `count` characters, without reading them
+    fn ahead(&self, count: usize) -> String {
+        self.string_iter
+            .clone()
+            .take(count)
+            .map(|(_, val)| val)
+            .collect()
+    }
+
+    /// Read the rest of an arrow with conditions after its left half e.g. `[Fe, 450C]->` after `-`,
+    /// queueing a condition token for each of them.
+    /// `rights` are the right halves the arrow can end with, longest first
+    fn conditioned_arrow(
+        &mut self,
+        loc: usize,
+        left: &str,
+        rights: &[(&str, Arrow)],
+    ) -> Result<Tokens, ParseError> {
+        let mut raw = left.to_string();
+        for below in [false, true] {
+            let Some(&(open, '[')) = self.string_iter.peek() else {
+                break;
+            };
+            self.string_iter.next();
+            raw.push('[');
+
+            // brackets inside a condition e.g. [Cu(NH3)4]^2+ do not end it
+            let mut depth = 0;
+            let mut start = open + 1;
+            let mut condition = String::new();
+            loop {
+                let Some((index, val)) = self.string_iter.next() else {
+                    self.pending.clear();
+                    return Err(ParseError::UnbalancedParen {
+                        span: Span::new(open, open + 1),
+                    });
+                };
+                raw.push(val);
+                match val {
+                    ',' | ']' if depth == 0 => {
+                        self.queue_condition(&condition, start, below);
+                        condition.clear();
+                        start = index + 1;
+                        if val == ']' {
+                            break;
+                        }
+                        continue;
+                    }
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    _ => {}
+                }
+                condition.push(val);
+            }
+        }
+
+        for &(right, data) in rights {
+            if self.ahead(right.len()) == right {
+                for _ in right.chars() {
+                    self.string_iter.next();
+                }
+                raw.push_str(right);
+                return Ok(Tokens::Yields {
+                    data,
+                    meta: TokenMetadata::new(&raw, loc),
+                });
+            }
+        }
+        self.pending.clear();
+        Err(ParseError::UnterminatedYields {
+            span: Span::new(loc, loc + raw.len()),
+        })
+    }
+
+    /// Queue a condition starting at `start` without the whitespace around it, unless it is empty
+    fn queue_condition(&mut self, condition: &str, start: usize, below: bool) {
+        let trimmed = condition.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+        let loc = start + condition.len() - condition.trim_start().len();
+        self.pending.push_back(Tokens::Condition {
+            data: trimmed.to_owned(),
+            below,
+            meta: TokenMetadata::new(trimmed, loc),
+        });
+    }
+
+    /// If the characters after the next `skip` spell a state up to its closing paren e.g. `aq)`,
+    /// return the state and the number of characters it spans
+    fn state_ahead(&self, skip: usize) -> Option<(State, usize)> {
+        let mut temp = String::new();
+        for (_, val) in self.string_iter.clone().skip(skip) {
+            match val {
+                'a'..='z' if temp.len() < 2 => temp.push(val),
+                ')' => return State::from_symbol(&temp).map(|state| (state, temp.len() + 1)),
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    /// Whether a trailing + or - is a charge rather than a plus or yields sign,
+    /// which is when nothing else of the formula unit follows it
+    fn ends_charge(&mut self) -> bool {
+        match self.string_iter.peek() {
+            None
+            | Some((
+                _,
+                '+' | '-' | ')' | ']' | '}' | '·' | '*' | '<' | '=' | '→' | '⟶' | '←' | '⇄' | '⇌',
+            )) => true,
+            Some((_, val)) if val.is_whitespace() => true,
+            Some((_, '(')) => self.state_ahead(1).is_some(),
+            Some(_) => false,
+        }
+    }
+
+    /// Whether another formula unit starts after the whitespace following a +,
+    /// e.g. the O2 of `2H2+ O2`, so that the + joins them rather than being a charge
+    fn unit_after_whitespace(&self) -> bool {
+        let spaces = self
+            .string_iter
+            .clone()
+            .take_while(|(_, val)| val.is_whitespace())
+            .count();
+        match self.string_iter.clone().nth(spaces) {
+            _ if spaces == 0 => false,
+            Some((_, val)) if val.is_ascii_uppercase() || val.is_ascii_digit() => true,
+            Some((_, '[' | '{')) => true,
+            Some((_, '(')) => self.state_ahead(spaces + 1).is_none(),
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -212,6 +602,7 @@ mod tests {
                 meta: TokenMetadata::new("3", 8),
             },
             Tokens::Yields {
+                data: Arrow::Forward,
                 meta: TokenMetadata::new("->", 9),
             },
             Tokens::Number {
@@ -241,4 +632,674 @@ mod tests {
 
         assert_eq!(exp, res.unwrap());
     }
+
+    #[test]
+    fn can_parse_caret_charges() {
+        let to_parse = &String::from("SO4^2-+Fe^3+");
+        let stream = LazyTokenStream::new(to_parse);
+
+        let res = Result::from(stream);
+
+        let exp = vec![
+            Tokens::Element {
+                data: "S".to_owned(),
+                meta: TokenMetadata::new("S", 0),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 1),
+            },
+            Tokens::Number {
+                data: 4,
+                meta: TokenMetadata::new("4", 2),
+            },
+            Tokens::Charge {
+                data: -2,
+                meta: TokenMetadata::new("^2-", 3),
+            },
+            Tokens::Plus {
+                meta: TokenMetadata::new("+", 6),
+            },
+            Tokens::Element {
+                data: "Fe".to_owned(),
+                meta: TokenMetadata::new("Fe", 7),
+            },
+            Tokens::Charge {
+                data: 3,
+                meta: TokenMetadata::new("^3+", 9),
+            },
+        ];
+
+        assert_eq!(Ok(exp), res);
+    }
+
+    #[test]
+    fn can_parse_trailing_charges() {
+        let to_parse = &String::from("NH4++OH-->H2O");
+        let stream = LazyTokenStream::new(to_parse);
+
+        let res = Result::from(stream);
+
+        let exp = vec![
+            Tokens::Element {
+                data: "N".to_owned(),
+                meta: TokenMetadata::new("N", 0),
+            },
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 1),
+            },
+            Tokens::Number {
+                data: 4,
+                meta: TokenMetadata::new("4", 2),
+            },
+            Tokens::Charge {
+                data: 1,
+                meta: TokenMetadata::new("+", 3),
+            },
+            Tokens::Plus {
+                meta: TokenMetadata::new("+", 4),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 5),
+            },
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 6),
+            },
+            Tokens::Charge {
+                data: -1,
+                meta: TokenMetadata::new("-", 7),
+            },
+            Tokens::Yields {
+                data: Arrow::Forward,
+                meta: TokenMetadata::new("->", 8),
+            },
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 10),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 11),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 12),
+            },
+        ];
+
+        assert_eq!(Ok(exp), res);
+    }
+
+    #[test]
+    fn rejects_charge_without_sign() {
+        let to_parse = &String::from("Fe^3");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse));
+
+        assert_eq!(
+            Err(ParseError::MissingChargeSign {
+                span: Span::new(2, 4)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn can_parse_states() {
+        let to_parse = &String::from("H2(g)+OH-(aq)");
+        let stream = LazyTokenStream::new(to_parse);
+
+        let res = Result::from(stream);
+
+        let exp = vec![
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 0),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 1),
+            },
+            Tokens::State {
+                data: State::Gas,
+                meta: TokenMetadata::new("(g)", 2),
+            },
+            Tokens::Plus {
+                meta: TokenMetadata::new("+", 5),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 6),
+            },
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 7),
+            },
+            Tokens::Charge {
+                data: -1,
+                meta: TokenMetadata::new("-", 8),
+            },
+            Tokens::State {
+                data: State::Aqueous,
+                meta: TokenMetadata::new("(aq)", 9),
+            },
+        ];
+
+        assert_eq!(Ok(exp), res);
+    }
+
+    #[test]
+    fn does_not_confuse_groups_with_states() {
+        let to_parse = &String::from("Na+(s");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse));
+
+        assert_eq!(
+            Err(ParseError::UnknownCharacter {
+                character: 's',
+                span: Span::new(4, 5)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn skips_whitespace() {
+        let to_parse = &String::from(" 2H2 +\tO2\n->  2H2O ");
+        let stream = LazyTokenStream::new(to_parse);
+
+        let res = Result::from(stream);
+
+        let exp = vec![
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 1),
+            },
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 2),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 3),
+            },
+            Tokens::Plus {
+                meta: TokenMetadata::new("+", 5),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 7),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 8),
+            },
+            Tokens::Yields {
+                data: Arrow::Forward,
+                meta: TokenMetadata::new("->", 10),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 14),
+            },
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 15),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("2", 16),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 17),
+            },
+        ];
+
+        let res: Vec<Tokens> = res.unwrap();
+        assert_eq!(exp, res);
+        let locations: Vec<usize> = res.iter().map(|token| token.meta().loc()).collect();
+        assert_eq!(vec![1, 2, 3, 5, 7, 8, 10, 14, 15, 16, 17], locations);
+    }
+
+    #[test]
+    fn can_parse_spaced_charges() {
+        let to_parse = &String::from("NH4+ + OH- -> NH3 + H2O");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+
+        assert_eq!(
+            Tokens::Charge {
+                data: 1,
+                meta: TokenMetadata::new("+", 3),
+            },
+            res[3]
+        );
+        assert_eq!(
+            Tokens::Plus {
+                meta: TokenMetadata::new("+", 5),
+            },
+            res[4]
+        );
+        assert_eq!(
+            Tokens::Charge {
+                data: -1,
+                meta: TokenMetadata::new("-", 9),
+            },
+            res[7]
+        );
+        assert_eq!(
+            Tokens::Yields {
+                data: Arrow::Forward,
+                meta: TokenMetadata::new("->", 11),
+            },
+            res[8]
+        );
+    }
+
+    #[test]
+    fn reads_a_plus_before_a_spaced_formula_unit() {
+        for source in [
+            "2H2+ O2 -> 2H2O",
+            "2H2 +O2 -> 2H2O",
+            "2H2+O2 -> 2H2O",
+            "H2O+ (OH)2",
+        ] {
+            let to_parse = &String::from(source);
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+            assert_eq!(
+                Tokens::Plus {
+                    meta: TokenMetadata::new("+", 3),
+                },
+                res[3],
+                "lexing {source}"
+            );
+        }
+
+        // a state or another sign after the space still ends a charge
+        for source in ["NH4+ (aq)", "NH4+ + OH-"] {
+            let to_parse = &String::from(source);
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+            assert_eq!(
+                Tokens::Charge {
+                    data: 1,
+                    meta: TokenMetadata::new("+", 3),
+                },
+                res[3],
+                "lexing {source}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_whitespace_inside_tokens() {
+        let to_parse = &String::from("F e");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse));
+        assert_eq!(
+            Err(ParseError::UnknownCharacter {
+                character: 'e',
+                span: Span::new(2, 3)
+            }),
+            res
+        );
+
+        let to_parse = &String::from("H 2O");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse));
+        assert_eq!(
+            Err(ParseError::UnexpectedWhitespace {
+                span: Span::new(1, 2)
+            }),
+            res
+        );
+
+        let to_parse = &String::from("1  2H2O");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse));
+        assert_eq!(
+            Err(ParseError::UnexpectedWhitespace {
+                span: Span::new(1, 3)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn errors_have_byte_spans() {
+        let cases = [
+            (
+                "Fe$",
+                ParseError::UnknownCharacter {
+                    character: '$',
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "H2-O",
+                ParseError::UnterminatedYields {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "Fe70000",
+                ParseError::NumberOverflow {
+                    span: Span::new(2, 7),
+                },
+            ),
+            (
+                "NaAbc",
+                ParseError::ElementTooLong {
+                    span: Span::new(2, 5),
+                },
+            ),
+            // spans count bytes, not characters
+            (
+                "H★",
+                ParseError::UnknownCharacter {
+                    character: '★',
+                    span: Span::new(1, 4),
+                },
+            ),
+        ];
+
+        for (to_parse, exp) in cases {
+            let to_parse = &String::from(to_parse);
+            let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse));
+            assert_eq!(Err(exp), res);
+        }
+    }
+
+    #[test]
+    fn can_parse_brackets() {
+        let to_parse = &String::from("K3[Fe{CN}6]");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+
+        let parens: Vec<(&ParenType, &BracketKind, usize)> = res
+            .iter()
+            .filter_map(|token| match token {
+                Tokens::Paren { data, kind, meta } => Some((data, kind, meta.loc())),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (&ParenType::OPEN, &BracketKind::Square, 2),
+                (&ParenType::OPEN, &BracketKind::Curly, 5),
+                (&ParenType::CLOSE, &BracketKind::Curly, 8),
+                (&ParenType::CLOSE, &BracketKind::Square, 10),
+            ],
+            parens
+        );
+    }
+
+    #[test]
+    fn can_parse_separators() {
+        let to_parse = &String::from("CuSO4·5H2O*NH3");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+
+        let separators: Vec<Span> = res
+            .iter()
+            .filter(|token| matches!(token, Tokens::Separator { .. }))
+            .map(|token| token.meta().span())
+            .collect();
+
+        assert_eq!(vec![Span::new(5, 7), Span::new(11, 12)], separators);
+    }
+
+    #[test]
+    fn can_parse_unicode_subscripts() {
+        let to_parse = &String::from("H₂SO₄");
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+
+        let exp = vec![
+            Tokens::Element {
+                data: "H".to_owned(),
+                meta: TokenMetadata::new("H", 0),
+            },
+            Tokens::Number {
+                data: 2,
+                meta: TokenMetadata::new("₂", 1),
+            },
+            Tokens::Element {
+                data: "S".to_owned(),
+                meta: TokenMetadata::new("S", 4),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 5),
+            },
+            Tokens::Number {
+                data: 4,
+                meta: TokenMetadata::new("₄", 6),
+            },
+        ];
+        assert_eq!(exp, res);
+        assert_eq!(Span::new(1, 4), res[1].meta().span());
+        assert_eq!(Span::new(6, 9), res[4].meta().span());
+    }
+
+    #[test]
+    fn can_parse_unicode_charges() {
+        let cases = [
+            ("SO₄²⁻", -2, "²⁻"),
+            ("NH₄⁺", 1, "⁺"),
+            ("Fe¹²³⁺", 123, "¹²³⁺"),
+        ];
+        for (to_parse, charge, raw) in cases {
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(to_parse)).unwrap();
+            let last = res.last().unwrap();
+            assert_eq!(
+                &Tokens::Charge {
+                    data: charge,
+                    meta: TokenMetadata::new(raw, 0),
+                },
+                last
+            );
+            assert_eq!(to_parse.len() - raw.len(), last.meta().loc());
+        }
+
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("SO₄²"));
+        assert_eq!(
+            Err(ParseError::MissingChargeSign {
+                span: Span::new(5, 7)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn can_parse_unicode_arrows() {
+        let cases = [
+            ("→", Arrow::Forward),
+            ("⟶", Arrow::Forward),
+            ("←", Arrow::Reverse),
+            ("⇄", Arrow::Reversible),
+            ("⇌", Arrow::Equilibrium),
+        ];
+        for (arrow, data) in cases {
+            let to_parse = format!("Na+ {} Na", arrow);
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(&to_parse)).unwrap();
+            assert_eq!(
+                Tokens::Yields {
+                    data,
+                    meta: TokenMetadata::new(arrow, 4),
+                },
+                res[2]
+            );
+            assert_eq!(Span::new(4, 4 + arrow.len()), res[2].meta().span());
+        }
+    }
+
+    #[test]
+    fn can_parse_arrow_kinds() {
+        let cases = [
+            ("->", Arrow::Forward),
+            ("<-", Arrow::Reverse),
+            ("<->", Arrow::Reversible),
+            ("<=>", Arrow::Equilibrium),
+            ("=", Arrow::Equals),
+        ];
+        for (arrow, data) in cases {
+            // the charge of OH- ends before the arrow
+            let to_parse = format!("OH-{}H2O", arrow);
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(&to_parse)).unwrap();
+            assert_eq!(
+                Tokens::Charge {
+                    data: -1,
+                    meta: TokenMetadata::new("-", 2),
+                },
+                res[2]
+            );
+            assert_eq!(
+                Tokens::Yields {
+                    data,
+                    meta: TokenMetadata::new(arrow, 3),
+                },
+                res[3]
+            );
+            assert_eq!(Span::new(3, 3 + arrow.len()), res[3].meta().span());
+        }
+    }
+
+    #[test]
+    fn rejects_unfinished_arrows() {
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("N2 <= N"));
+        assert_eq!(
+            Err(ParseError::UnterminatedYields {
+                span: Span::new(3, 5)
+            }),
+            res
+        );
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("N2 < N"));
+        assert_eq!(
+            Err(ParseError::UnknownCharacter {
+                character: '<',
+                span: Span::new(3, 4)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn can_parse_arrow_conditions() {
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(
+            "N2 -[Fe, 450C][ H2O ]-> NH3",
+        ))
+        .unwrap();
+        assert_eq!(
+            Tokens::Yields {
+                data: Arrow::Forward,
+                meta: TokenMetadata::new("-[Fe, 450C][ H2O ]->", 3),
+            },
+            res[2]
+        );
+        let conditions = [("Fe", false, 5), ("450C", false, 9), ("H2O", true, 16)];
+        for (token, (data, below, loc)) in res[3..6].iter().zip(conditions) {
+            assert_eq!(
+                &Tokens::Condition {
+                    data: data.to_owned(),
+                    below,
+                    meta: TokenMetadata::new(data, loc),
+                },
+                token
+            );
+            assert_eq!(loc, token.meta().loc());
+        }
+        assert_eq!(
+            Tokens::Element {
+                data: "N".to_owned(),
+                meta: TokenMetadata::new("N", 24),
+            },
+            res[6]
+        );
+
+        let cases = [
+            ("<-[Pt]-", Arrow::Reverse),
+            ("<-[Pt]->", Arrow::Reversible),
+            ("<=[Pt]=>", Arrow::Equilibrium),
+            ("=[Pt]=", Arrow::Equals),
+        ];
+        for (arrow, data) in cases {
+            let to_parse = format!("A{}B", arrow);
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(&to_parse)).unwrap();
+            assert_eq!(
+                Tokens::Yields {
+                    data,
+                    meta: TokenMetadata::new(arrow, 1),
+                },
+                res[1]
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unfinished_conditions() {
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("N2 -[Fe NH3"));
+        assert_eq!(
+            Err(ParseError::UnbalancedParen {
+                span: Span::new(4, 5)
+            }),
+            res
+        );
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("N2 -[Fe] NH3"));
+        assert_eq!(
+            Err(ParseError::UnterminatedYields {
+                span: Span::new(3, 8)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn can_parse_electrons() {
+        for raw in ["e-", "e⁻", "e^-"] {
+            let to_parse = format!("2{} + Cu^2+", raw);
+            let res =
+                Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new(&to_parse)).unwrap();
+            assert_eq!(
+                Tokens::Electron {
+                    meta: TokenMetadata::new(raw, 1),
+                },
+                res[1]
+            );
+            assert_eq!(Span::new(1, 1 + raw.len()), res[1].meta().span());
+            assert_eq!(
+                Tokens::Plus {
+                    meta: TokenMetadata::new("+", 0)
+                },
+                res[2]
+            );
+        }
+
+        // the electron's sign is not the start of the arrow
+        let res =
+            Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("Cu^2++2e-->Cu")).unwrap();
+        assert_eq!(
+            Tokens::Electron {
+                meta: TokenMetadata::new("e-", 7),
+            },
+            res[4]
+        );
+        assert_eq!(
+            Tokens::Yields {
+                data: Arrow::Forward,
+                meta: TokenMetadata::new("->", 9),
+            },
+            res[5]
+        );
+
+        let res = Result::<Vec<Tokens>, ParseError>::from(LazyTokenStream::new("e->Cu"));
+        assert_eq!(
+            Err(ParseError::UnknownCharacter {
+                character: 'e',
+                span: Span::new(0, 1)
+            }),
+            res
+        );
+    }
 }
@@ -1,14 +1,52 @@
-#![feature(box_patterns)]
-
 pub mod ast_types;
+pub mod balance;
+pub mod composition;
+pub mod diagnostic;
+pub mod error;
 pub mod lexer;
+pub mod mass;
+pub mod mhchem;
 pub mod parser;
+pub mod periodic_table;
+pub mod render;
 pub mod token_types;
+mod unicode;
 
-use self::{ast_types::Node, lexer::LazyTokenStream};
+use self::{
+    ast_types::{Equation, FormulaUnit, Node},
+    error::ParseError,
+    lexer::LazyTokenStream,
+};
 
 /// Parse a string and return a result with either the node or the error
-pub fn parse(string: String) -> Result<Box<Node>, String> {
+pub fn parse(string: String) -> Result<Node, ParseError> {
     let stream = LazyTokenStream::new(&string);
     parser::parse(stream)
 }
+
+/// Parse a string which should be a single formula unit e.g. `Fe2O3`
+pub fn parse_formula(string: String) -> Result<FormulaUnit, ParseError> {
+    let stream = LazyTokenStream::new(&string);
+    parser::parse_formula(stream)
+}
+
+/// Parse a string which should be an equation e.g. `4Fe+3O2->2Fe2O3`
+pub fn parse_equation(string: String) -> Result<Equation, ParseError> {
+    let stream = LazyTokenStream::new(&string);
+    parser::parse_equation(stream)
+}
+
+/// Parse mhchem markup e.g. `\ce{2H2 + O2 -> 2H2O}` into the same node `parse` returns for
+/// the plain syntax. Spans point into the markup, see `mhchem`
+pub fn parse_mhchem(string: String) -> Result<Node, ParseError> {
+    mhchem::parse(&string)
+}
+
+/// Parse a string like `parse`, but also reject element symbols
+/// which are not in the periodic table
+pub fn parse_validated(string: String) -> Result<Node, ParseError> {
+    let stream = LazyTokenStream::new(&string);
+    let node = parser::parse(periodic_table::validate(stream))?;
+    periodic_table::validate_conditions(&node)?;
+    Ok(node)
+}
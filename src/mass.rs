@@ -0,0 +1,193 @@
+use crate::{
+    ast_types::{Equation, FormulaUnit, Node, Side},
+    composition::{Composition, CompositionError},
+    periodic_table,
+};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+/// The total molar masses of both sides of an equation, in g/mol
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SideMasses {
+    pub reactants: f64,
+    pub products: f64,
+}
+
+impl SideMasses {
+    /// The products mass minus the reactants mass
+    pub fn difference(&self) -> f64 {
+        self.products - self.reactants
+    }
+
+    /// Whether both sides have the same mass, within `tolerance` g/mol
+    pub fn is_conserved(&self, tolerance: f64) -> bool {
+        self.difference().abs() <= tolerance
+    }
+}
+
+/// The reasons a molar mass can not be calculated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MassError {
+    /// `molar_mass` was given a `Node::Equation`
+    NotAFormula,
+    /// `side_masses` was given a `Node::Formula`
+    NotAnEquation,
+    /// The symbol is not in the periodic table
+    UnknownElement(String),
+    /// An atom count does not fit in a u32
+    Overflow,
+}
+
+impl Display for MassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAFormula => write!(f, "An equation has no single molar mass"),
+            Self::NotAnEquation => write!(f, "Expected an equation"),
+            Self::UnknownElement(symbol) => write!(f, "Unknown element: {}", symbol),
+            Self::Overflow => write!(f, "Atom count overflowed"),
+        }
+    }
+}
+
+impl Error for MassError {}
+
+impl From<CompositionError> for MassError {
+    fn from(error: CompositionError) -> Self {
+        match error {
+            CompositionError::NotAFormula => Self::NotAFormula,
+            CompositionError::Overflow => Self::Overflow,
+        }
+    }
+}
+
+/// Calculate the molar mass of a node in g/mol.
+/// Coefficients of formula units are included, so the mass of 2H2O is twice that of H2O.
+/// A whole equation has no single molar mass, use `side_masses`
+pub fn molar_mass(node: &Node) -> Result<f64, MassError> {
+    match node {
+        Node::Formula(unit) => unit.molar_mass(),
+        Node::Equation(_) => Err(MassError::NotAFormula),
+    }
+}
+
+/// Calculate the total molar mass of each side of an equation in g/mol
+pub fn side_masses(node: &Node) -> Result<SideMasses, MassError> {
+    match node {
+        Node::Equation(equation) => equation.side_masses(),
+        _ => Err(MassError::NotAnEquation),
+    }
+}
+
+/// Sum the atomic weights from 0.0, as an empty float sum is -0.0 e.g. for electrons
+fn mass(composition: Composition) -> Result<f64, MassError> {
+    composition.iter().try_fold(0.0, |total, (symbol, count)| {
+        periodic_table::lookup(symbol)
+            .map(|element| total + element.atomic_weight * *count as f64)
+            .ok_or_else(|| MassError::UnknownElement(symbol.clone()))
+    })
+}
+
+impl Node {
+    /// See `mass::molar_mass`
+    pub fn molar_mass(&self) -> Result<f64, MassError> {
+        molar_mass(self)
+    }
+
+    /// See `mass::side_masses`
+    pub fn side_masses(&self) -> Result<SideMasses, MassError> {
+        side_masses(self)
+    }
+}
+
+impl FormulaUnit {
+    /// See `mass::molar_mass`
+    pub fn molar_mass(&self) -> Result<f64, MassError> {
+        mass(self.composition()?)
+    }
+}
+
+impl Side {
+    /// The sum of the molar masses of every formula unit
+    pub fn molar_mass(&self) -> Result<f64, MassError> {
+        mass(self.composition()?)
+    }
+}
+
+impl Equation {
+    /// See `mass::side_masses`
+    pub fn side_masses(&self) -> Result<SideMasses, MassError> {
+        Ok(SideMasses {
+            reactants: self.reactants().molar_mass()?,
+            products: self.products().molar_mass()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MassError;
+    use crate::parse;
+
+    fn assert_close(expected: f64, actual: f64) {
+        assert!(
+            (expected - actual).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn can_calculate_formula_unit_mass() {
+        let ast = parse(String::from("Fe2O3")).unwrap();
+        assert_close(159.687, ast.molar_mass().unwrap());
+    }
+
+    #[test]
+    fn includes_coefficients() {
+        let ast = parse(String::from("2H2O")).unwrap();
+        assert_close(36.030, ast.molar_mass().unwrap());
+    }
+
+    #[test]
+    fn includes_group_subscripts() {
+        let ast = parse(String::from("Mg(NO3)2")).unwrap();
+        assert_close(148.313, ast.molar_mass().unwrap());
+    }
+
+    #[test]
+    fn adds_hydrate_water() {
+        let ast = parse(String::from("CuSO4*5H2O")).unwrap();
+        assert_close(249.677, ast.molar_mass().unwrap());
+    }
+
+    #[test]
+    fn can_calculate_side_masses() {
+        let ast = parse(String::from("4Fe+3O2->2Fe2O3")).unwrap();
+        let masses = ast.side_masses().unwrap();
+
+        assert_close(319.374, masses.reactants);
+        assert_close(319.374, masses.products);
+        assert!(masses.is_conserved(1e-6));
+        assert!(ast.molar_mass().is_err());
+    }
+
+    #[test]
+    fn electrons_have_no_mass() {
+        let ast = parse(String::from("2e-")).unwrap();
+        assert_eq!(Ok(0.0), ast.molar_mass());
+
+        let ast = parse(String::from("Cu^2++2e-->Cu")).unwrap();
+        assert!(ast.side_masses().unwrap().is_conserved(1e-6));
+    }
+
+    #[test]
+    fn rejects_unknown_elements() {
+        let ast = parse(String::from("Xx2")).unwrap();
+        assert_eq!(
+            Err(MassError::UnknownElement("Xx".to_owned())),
+            ast.molar_mass()
+        );
+        assert_eq!(Err(MassError::NotAnEquation), ast.side_masses());
+    }
+}
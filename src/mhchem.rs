@@ -0,0 +1,347 @@
+//! A front-end for mhchem's `\ce{...}` markup, e.g. `\ce{2H2 + O2 -> 2H2O}`.
+//! The markup is rewritten into the plain syntax read by `LazyTokenStream`, remembering which
+//! characters of the markup each rewritten byte came from, so that tokens, errors and the
+//! spans of the AST point into the original markup.
+//!
+//! Rewritten forms:
+//! - the `\ce{` and `}` wrapper, which is optional
+//! - `^{2+}` charges become `^2+`
+//! - `_{2}` and `_2` subscripts become `2`
+//! - `.` between the parts of an adduct becomes `*`
+//! - `\{` and `\}` become curly brackets
+//! - the unbalanced equilibrium arrows `<=>>` and `<<=>` become `<=>`
+//! - the reversible arrow `<-->` becomes `<->`
+//! - conditions after an arrow move inside it e.g. `->[Fe][450C]` becomes `-[Fe][450C]->`
+//! - escaped characters in conditions are unescaped e.g. `->[50\%]` becomes `-[50%]->`
+
+use crate::{
+    ast_types::{Arrow, Node},
+    error::{ParseError, Span},
+    lexer::LazyTokenStream,
+    parser,
+    token_types::TokenMetadata,
+};
+
+/// Parse mhchem markup into the same node `parse` returns for the plain syntax
+pub fn parse(source: &str) -> Result<Node, ParseError> {
+    let translation = Translation::new(source);
+    let stream = LazyTokenStream::new(&translation.text).map(|token| match token {
+        Ok(mut token) => {
+            let span = translation.original(token.meta().span());
+            *token.meta_mut() = TokenMetadata::new(&source[span.start..span.end], span.start);
+            Ok(token)
+        }
+        Err(mut error) => {
+            *error.span_mut() = translation.original(error.span());
+            Err(error)
+        }
+    });
+    parser::parse(stream)
+}
+
+/// The characters with a meaning in LaTeX, and how to write them in text
+pub(crate) const LATEX_ESCAPES: [(char, &str); 10] = [
+    ('\\', "\\textbackslash{}"),
+    ('%', "\\%"),
+    ('&', "\\&"),
+    ('#', "\\#"),
+    ('$', "\\$"),
+    ('_', "\\_"),
+    ('{', "\\{"),
+    ('}', "\\}"),
+    ('~', "\\textasciitilde{}"),
+    ('^', "\\textasciicircum{}"),
+];
+
+/// The arrows of mhchem, longest first, and the arrows they are read as
+const ARROWS: [(&str, Arrow); 7] = [
+    ("<=>>", Arrow::Equilibrium),
+    ("<<=>", Arrow::Equilibrium),
+    ("<-->", Arrow::Reversible),
+    ("<=>", Arrow::Equilibrium),
+    ("<->", Arrow::Reversible),
+    ("->", Arrow::Forward),
+    ("<-", Arrow::Reverse),
+];
+
+/// The plain syntax for some markup, and where each of its bytes came from in the markup
+struct Translation {
+    text: String,
+    /// The span of the markup each byte of `text` was rewritten from
+    origins: Vec<Span>,
+    /// The end of the markup inside the wrapper
+    end: usize,
+}
+
+impl Translation {
+    fn new(source: &str) -> Self {
+        let (start, end) = unwrap(source);
+        let mut translation = Self {
+            text: String::new(),
+            origins: vec![],
+            end,
+        };
+
+        let mut chars = source[start..end]
+            .char_indices()
+            .map(|(index, val)| (index + start, val))
+            .peekable();
+        // the start of markup dropped before the next character e.g. the `_{` of `_{2}`
+        let mut dropped: Option<usize> = None;
+        // whether a `^{` or `_{` has not been closed yet
+        let mut in_braces = false;
+        // inside the conditions of an arrow, how deeply nested in brackets,
+        // the right half of the arrow and the span of the arrow
+        let mut conditions: Option<(usize, &str, Span)> = None;
+
+        while let Some((index, val)) = chars.next() {
+            let rest = &source[index..end];
+            let arrow = ARROWS
+                .into_iter()
+                .find(|(arrow, _)| rest.starts_with(arrow));
+            let escape = LATEX_ESCAPES
+                .into_iter()
+                .find(|(_, escape)| rest.starts_with(escape));
+            match val {
+                '^' if rest.starts_with("^{") => {
+                    chars.next();
+                    in_braces = true;
+                    translation.push('^', Span::new(index, index + 2));
+                }
+                '_' => {
+                    if rest.starts_with("_{") {
+                        chars.next();
+                        in_braces = true;
+                    }
+                    dropped.get_or_insert(index);
+                }
+                '}' if in_braces => {
+                    in_braces = false;
+                    if let Some(last) = translation.origins.last_mut() {
+                        last.end = index + 1;
+                    }
+                }
+                '\\' if rest.starts_with("\\{") || rest.starts_with("\\}") => {
+                    let (_, bracket) = chars.next().unwrap();
+                    translation.push(bracket, Span::new(index, index + 2));
+                }
+                '\\' if conditions.is_some() && escape.is_some() => {
+                    let (unescaped, escape) = escape.unwrap();
+                    for _ in 1..escape.len() {
+                        chars.next();
+                    }
+                    translation.push(unescaped, Span::new(index, index + escape.len()));
+                }
+                '<' | '-' if arrow.is_some() => {
+                    let (markup, arrow) = arrow.unwrap();
+                    for _ in 1..markup.len() {
+                        chars.next();
+                    }
+                    let span = Span::new(index, index + markup.len());
+                    if rest[markup.len()..].starts_with('[') {
+                        let (left, right) = arrow.halves();
+                        translation.push_str(left, span);
+                        conditions = Some((0, right, span));
+                    } else {
+                        translation.push_str(arrow.symbol(), span);
+                    }
+                }
+                '[' if conditions.is_some() => {
+                    if let Some((depth, ..)) = &mut conditions {
+                        *depth += 1;
+                    }
+                    translation.push(val, Span::new(index, index + 1));
+                }
+                ']' if conditions.is_some() => {
+                    translation.push(val, Span::new(index, index + 1));
+                    if let Some((depth, right, span)) = &mut conditions {
+                        *depth -= 1;
+                        if *depth == 0 && !rest[1..].starts_with('[') {
+                            translation.push_str(right, *span);
+                            conditions = None;
+                        }
+                    }
+                }
+                // outside of conditions, which can have decimal points e.g. 1.5 atm
+                '.' if conditions.is_none() => translation.push('*', Span::new(index, index + 1)),
+                _ => {
+                    let start = dropped.take().unwrap_or(index);
+                    translation.push(val, Span::new(start, index + val.len_utf8()));
+                }
+            }
+        }
+        translation
+    }
+
+    fn push(&mut self, val: char, origin: Span) {
+        self.text.push(val);
+        for _ in 0..val.len_utf8() {
+            self.origins.push(origin);
+        }
+    }
+
+    fn push_str(&mut self, text: &str, origin: Span) {
+        for val in text.chars() {
+            self.push(val, origin);
+        }
+    }
+
+    /// The span of the markup a span of `text` was rewritten from
+    fn original(&self, span: Span) -> Span {
+        let start = self
+            .origins
+            .get(span.start)
+            .map_or(self.end, |origin| origin.start);
+        if span.is_empty() {
+            return Span::new(start, start);
+        }
+        Span::new(start, self.origins[span.end - 1].end)
+    }
+}
+
+/// The start and end of the markup inside a `\ce{...}` wrapper, or all of it without one
+fn unwrap(source: &str) -> (usize, usize) {
+    let trimmed = source.trim();
+    let offset = source.len() - source.trim_start().len();
+    match trimmed
+        .strip_prefix("\\ce{")
+        .and_then(|inner| inner.strip_suffix('}'))
+    {
+        Some(inner) => (offset + 4, offset + 4 + inner.len()),
+        None => (0, source.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast_types::Term;
+
+    fn plain(source: &str) -> Result<Node, ParseError> {
+        crate::parse(source.to_owned())
+    }
+
+    #[test]
+    fn parses_like_the_plain_syntax() {
+        let cases = [
+            (r"\ce{2H2 + O2 -> 2H2O}", "2H2+O2->2H2O"),
+            (r"\ce{SO4^{2-}}", "SO4^2-"),
+            (r"\ce{SO4^2-}", "SO4^2-"),
+            (r"\ce{Fe_{2}O_3}", "Fe2O3"),
+            (r"\ce{NH4^{+} + OH^{-} -> NH3 + H2O}", "NH4^++OH^-->NH3+H2O"),
+            (r"\ce{CuSO4.5H2O(s)}", "CuSO4*5H2O(s)"),
+            (r"\ce{[Cu\{NH3\}4]^{2+}}", "[Cu{NH3}4]^2+"),
+            (r"\ce{N2 + 3H2 <=> 2NH3}", "N2+3H2<=>2NH3"),
+            (r"\ce{N2 + 3H2 <=>> 2NH3}", "N2+3H2<=>2NH3"),
+            (r"\ce{H2O <--> H^+ + OH^-}", "H2O<->H^++OH^-"),
+            (r"\ce{2H2O <- 2H2 + O2}", "2H2O<-2H2+O2"),
+            (
+                r"\ce{N2 + 3H2 ->[Fe][450C] 2NH3}",
+                "N2+3H2-[Fe][450C]->2NH3",
+            ),
+            (r"\ce{N2 + 3H2 <=>>[Fe] 2NH3}", "N2+3H2<=[Fe]=>2NH3"),
+            (r"\ce{H2O2 <-->[MnO2] H2O}", "H2O2<-[MnO2]->H2O"),
+            (r"\ce{A ->[1.5 atm] B}", "A-[1.5 atm]->B"),
+            (r"\ce{Cu^{2+} + 2e^{-} -> Cu}", "Cu^2++2e-->Cu"),
+            (r"  \ce{2 H2O}  ", "2H2O"),
+            ("Ca(OH)2", "Ca(OH)2"),
+        ];
+        for (markup, exp) in cases {
+            assert_eq!(plain(exp), parse(markup), "parsing {markup}");
+        }
+    }
+
+    #[test]
+    fn parses_rendered_markup() {
+        for source in [
+            "2H2+O2->2H2O",
+            "Ca(NO3^-)2^2+(aq)",
+            "[Cu{NH3}4]SO4*5H2O",
+            "H2O<->H^++OH^-",
+            "N2+3H2<=>2NH3",
+            "2KClO3-[MnO2][heat]->2KCl+3O2",
+            "E-[[Cu(NH3)4]^2+]->F",
+            "Cu^2++2e-->Cu",
+            "A-[50% H2SO4 & heat_1]->B",
+            r"A-[#1 {x} ~$5^2 \ y]->B",
+            r"A-[\Delta]->B",
+            r"A-[\ce{H2O}]->B",
+        ] {
+            let node = plain(source).unwrap();
+            assert_eq!(Ok(node.clone()), parse(&node.to_mhchem()));
+        }
+    }
+
+    #[test]
+    fn parses_every_rendered_arrow_with_conditions() {
+        for arrow in [
+            Arrow::Forward,
+            Arrow::Reverse,
+            Arrow::Reversible,
+            Arrow::Equilibrium,
+            Arrow::Equals,
+        ] {
+            let (left, right) = arrow.halves();
+            let node = plain(&format!("A{}[Fe][heat]{}B", left, right)).unwrap();
+            // there is no conditioned `=` in mhchem, so it comes back as `->`
+            let exp = match arrow {
+                Arrow::Equals => plain("A-[Fe][heat]->B"),
+                _ => Ok(node.clone()),
+            };
+            assert_eq!(
+                exp,
+                parse(&node.to_mhchem()),
+                "rendering {}",
+                node.to_mhchem()
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_latex_commands_in_conditions() {
+        for markup in [r"\ce{A ->[\Delta] B}", r"\ce{A ->[\ce{H2O}][$\Delta$] B}"] {
+            let node = parse(markup).unwrap();
+            assert_eq!(markup, node.to_mhchem());
+            assert_eq!(Ok(node.clone()), parse(&node.to_mhchem()));
+        }
+    }
+
+    #[test]
+    fn spans_point_into_the_markup() {
+        let markup = r"\ce{Fe_{2}O3^{2-}}";
+        let Node::Formula(unit) = parse(markup).unwrap() else {
+            panic!("Expected a formula unit");
+        };
+        assert_eq!(Span::new(4, 17), unit.span());
+
+        let Term::Element(iron) = &unit.parts()[0].terms()[0] else {
+            panic!("Expected an element");
+        };
+        assert_eq!(Span::new(4, 10), iron.span());
+    }
+
+    #[test]
+    fn errors_point_into_the_markup() {
+        assert_eq!(
+            Err(ParseError::UnknownCharacter {
+                character: '$',
+                span: Span::new(13, 14),
+            }),
+            parse(r"\ce{H_{2} + O$}")
+        );
+        assert_eq!(
+            Err(ParseError::UnexpectedToken {
+                found: "^{2+}".to_owned(),
+                span: Span::new(4, 9),
+            }),
+            parse(r"\ce{^{2+}H}")
+        );
+        assert_eq!(
+            Err(ParseError::UnexpectedEnd {
+                expected: "an arrow".to_owned(),
+                span: Span::new(11, 11),
+            }),
+            parse(r"\ce{H2 + O2 }")
+        );
+    }
+}
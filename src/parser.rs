@@ -1,143 +1,363 @@
-use crate::ast_types::Node;
-use crate::token_types::Tokens;
+//! A recursive descent parser for the grammar below, written in EBNF.
+//! Whitespace is skipped by the lexer, and `charge` and `state` are single tokens e.g. `^2-` and `(aq)`.
+//!
+//! ```ebnf
+//! input        = formula unit | equation ;
+//! equation     = side , arrow , side ;
+//! arrow        = "->" | "<-" | "<->" | "<=>" | "="
+//!              | left , conditions , [ conditions ] , right ;
+//! conditions   = "[" , [ condition , { "," , condition } ] , "]" ;
+//! side         = formula unit , { "+" , formula unit } ;
+//! formula unit = [ number ] , part , { separator , [ number ] , part } , [ state ]
+//!              | [ number ] , electron ;
+//! part         = term , { term } , [ charge ] ;
+//! separator    = "·" | "*" ;
+//! term         = element , [ number ]
+//!              | open , term , { term } , [ charge ] , close , [ number ] ;
+//! electron     = "e-" | "e⁻" | "e^-" ;
+//! open         = "(" | "[" | "{" ;
+//! close        = ")" | "]" | "}" ;
+//! ```
+//!
+//! A group must be closed with the same kind of bracket it was opened with.
+//! The unicode arrows `→`, `⟶`, `←`, `⇄` and `⇌` are read as the arrows above.
+//!
+//! Conditions are written inside an arrow, split as `-[Fe]->`, `<-[Fe]-`, `<-[Fe]->`, `<=[Fe]=>`
+//! or `=[Fe]=`, with what goes above the arrow in the first brackets and below in the second.
+//! The lexer reads an arrow as one token followed by a token for each condition,
+//! and a condition that reads as a formula unit without a coefficient is a species, otherwise text.
+
+use crate::ast_types::{
+    Condition, Electron, ElementCount, Equation, FormulaUnit, Group, Node, Part, Side, Species,
+    Term,
+};
+use crate::error::{ParseError, Span};
+use crate::lexer::LazyTokenStream;
+use crate::token_types::{ParenType, TokenMetadata, Tokens};
+use std::iter::Peekable;
 
 /// Using an iterator (usually `LazyTokenStream`), parse tokens and return a result with the root node
-pub fn parse<'a, T: Iterator<Item = Result<Tokens, String>>>(
+pub fn parse<T: Iterator<Item = Result<Tokens, ParseError>>>(
     stream: T,
-) -> Result<Box<Node>, String> {
-    let mut stream = stream.peekable();
-    let mut paren_level = 0;
-    let mut current_stack = vec![Box::new(Node::ForumulaUnit(1, vec![]))];
-    loop {
-        match stream.next() {
-            Some(Err(val)) => return Err(val),
-            Some(Ok(Tokens::Number { data, meta })) => {
-                let fu = current_stack.pop().unwrap();
-                if let box Node::ForumulaUnit(_, vec) = fu {
-                    current_stack.push(Box::new(Node::ForumulaUnit(data, vec)));
-                } else {
-                    return Err("Invalid parent".to_owned());
-                }
-            }
-            Some(Ok(Tokens::Element { data, meta })) => {
-                let mut fu = *current_stack.pop().unwrap();
-
-                if let Node::ForumulaUnit(_, ref mut vec) = fu {
-                    if let Some(Ok(Tokens::Number { data: count, meta })) = stream.peek() {
-                        vec.push(Node::Element(*count, data));
-                        stream.next();
-                    } else {
-                        vec.push(Node::Element(1, data));
-                    }
-                    current_stack.push(Box::new(fu));
+) -> Result<Node, ParseError> {
+    Parser::new(stream).input()
+}
+
+/// Parse tokens which should be a single formula unit
+pub fn parse_formula<T: Iterator<Item = Result<Tokens, ParseError>>>(
+    stream: T,
+) -> Result<FormulaUnit, ParseError> {
+    let mut parser = Parser::new(stream);
+    let unit = parser.formula_unit()?;
+    parser.end_of_input()?;
+    Ok(unit)
+}
+
+/// Parse tokens which should be an equation
+pub fn parse_equation<T: Iterator<Item = Result<Tokens, ParseError>>>(
+    stream: T,
+) -> Result<Equation, ParseError> {
+    let mut parser = Parser::new(stream);
+    let reactants = parser.side()?;
+    parser.equation(reactants)
+}
+
+struct Parser<T: Iterator<Item = Result<Tokens, ParseError>>> {
+    stream: Peekable<T>,
+    /// The end of the last token taken, where an unexpected end of input is reported
+    end: usize,
+}
+
+impl<T: Iterator<Item = Result<Tokens, ParseError>>> Parser<T> {
+    fn new(stream: T) -> Self {
+        Self {
+            stream: stream.peekable(),
+            end: 0,
+        }
+    }
+
+    /// input = formula unit | equation
+    fn input(&mut self) -> Result<Node, ParseError> {
+        let mut reactants = self.side()?;
+        if reactants.len() == 1 && self.peek()?.is_none() {
+            return Ok(Node::Formula(reactants.pop().unwrap()));
+        }
+        self.equation(reactants).map(Node::Equation)
+    }
+
+    /// The rest of an equation after the reactants, arrow , side
+    fn equation(&mut self, reactants: Vec<FormulaUnit>) -> Result<Equation, ParseError> {
+        let arrow = match self.next()? {
+            Some(Tokens::Yields { data, .. }) => data,
+            // reactants without products
+            None => return Err(self.unexpected_end("an arrow")),
+            Some(token) => return Err(unexpected(&token)),
+        };
+        let (mut above, mut below) = (vec![], vec![]);
+        while let Some(Tokens::Condition { .. }) = self.peek()? {
+            if let Some(Tokens::Condition {
+                data,
+                below: is_below,
+                meta,
+            }) = self.next()?
+            {
+                let condition = condition(&data, meta.loc());
+                if is_below {
+                    below.push(condition);
                 } else {
-                    return Err("Invalid Parent".to_owned());
+                    above.push(condition);
                 }
             }
-            Some(Ok(Tokens::Plus { meta })) => {
-                let fu = *current_stack.pop().unwrap();
-                let mut maybe_reactants = current_stack.pop().map(|val| *val);
-                match maybe_reactants {
-                    Some(Node::Reactants(ref mut vec)) => {
-                        vec.push(fu);
-                        current_stack.push(Box::new(maybe_reactants.unwrap()));
-                        current_stack.push(Box::new(Node::ForumulaUnit(1, vec![])));
-                    }
-                    Some(Node::Products(ref mut vec)) => {
-                        vec.push(fu);
-                        current_stack.push(Box::new(maybe_reactants.unwrap()));
-                        current_stack.push(Box::new(Node::ForumulaUnit(1, vec![])));
-                    }
-                    Some(_) => return Err("Invalid plus".to_owned()),
-                    None => {
-                        if let Node::ForumulaUnit(_, _) = fu {
-                            current_stack.push(Box::new(Node::Reactants(vec![fu])));
-                            current_stack.push(Box::new(Node::ForumulaUnit(1, vec![])));
-                        }
-                    }
-                }
+        }
+        let products = self.side()?;
+        self.end_of_input()?;
+
+        let (reactants, products) = (side(reactants), side(products));
+        let span = reactants.span().to(products.span());
+        Ok(Equation::new(reactants, products)
+            .with_arrow(arrow)
+            .with_conditions(above, below)
+            .with_span(span))
+    }
+
+    /// side = formula unit , { "+" , formula unit }
+    fn side(&mut self) -> Result<Vec<FormulaUnit>, ParseError> {
+        let mut units = vec![self.formula_unit()?];
+        while let Some(Tokens::Plus { .. }) = self.peek()? {
+            self.next()?;
+            units.push(self.formula_unit()?);
+        }
+        Ok(units)
+    }
+
+    /// formula unit = [ number ] , part , { separator , [ number ] , part } , [ state ]
+    ///                | [ number ] , electron
+    fn formula_unit(&mut self) -> Result<FormulaUnit, ParseError> {
+        let start = self.start()?;
+        let coefficient = self.number()?;
+        if let Some(Tokens::Electron { meta }) = self.peek()? {
+            let span = meta.span();
+            self.next()?;
+            let electron = Species::Electron(Electron::new().with_span(span));
+            let unit = FormulaUnit::from_species(coefficient, electron, None).unwrap();
+            return Ok(unit.with_span(self.span(start)));
+        }
+        let mut parts = vec![self.part(false)?];
+        while let Some(Tokens::Separator { .. }) = self.peek()? {
+            self.next()?;
+            parts.push(self.part(true)?);
+        }
+
+        let state = match self.peek()? {
+            Some(Tokens::State { data, .. }) => {
+                let state = *data;
+                self.next()?;
+                Some(state)
             }
-            Some(Ok(Tokens::Yields { meta })) => {
-                let fu = *current_stack.pop().unwrap();
-                let mut maybe_reactants = current_stack.pop();
-
-                match maybe_reactants {
-                    Some(box Node::Reactants(ref mut vec)) => {
-                        vec.push(fu);
-                        current_stack.push(maybe_reactants.unwrap());
-                        current_stack.push(Box::new(Node::Products(vec![])));
-                        current_stack.push(Box::new(Node::ForumulaUnit(1, vec![])));
-                    }
-                    Some(_) => return Err("Invalid yields location".to_owned()),
-                    None => {
-                        current_stack.push(Box::new(Node::Reactants(vec![fu])));
-                        current_stack.push(Box::new(Node::ForumulaUnit(1, vec![])));
-                    }
+            _ => None,
+        };
+        let unit = FormulaUnit::adduct(coefficient, parts, state).unwrap();
+        Ok(unit.with_span(self.span(start)))
+    }
+
+    /// part = term , { term } , [ charge ]
+    ///
+    /// Parts after a separator can start with a count e.g. 5H2O
+    fn part(&mut self, counted: bool) -> Result<Part, ParseError> {
+        let start = self.start()?;
+        let count = if counted { self.number()? } else { 1 };
+        let terms = self.terms()?;
+        let charge = self.charge()?;
+        // terms are never empty, an error is returned instead
+        Ok(Part::new(count, terms, charge)
+            .unwrap()
+            .with_span(self.span(start)))
+    }
+
+    /// term , { term }
+    fn terms(&mut self) -> Result<Vec<Term>, ParseError> {
+        let mut terms = vec![];
+        loop {
+            match self.peek()? {
+                Some(Tokens::Element { .. }) => {
+                    let Some(Tokens::Element { data, meta }) = self.next()? else {
+                        unreachable!()
+                    };
+                    let element = ElementCount::new(data, self.number()?);
+                    let span = self.span(meta.loc());
+                    terms.push(Term::Element(element.with_span(span)));
                 }
+                Some(Tokens::Paren {
+                    data: ParenType::OPEN,
+                    ..
+                }) => terms.push(Term::Group(self.group()?)),
+                _ => break,
             }
-            Some(Ok(Tokens::Paren {
-                data: super::token_types::ParenType::OPEN,
-                meta,
-            })) => {
-                paren_level += 1;
-                current_stack.push(Box::new(Node::Group(1, vec![])));
+        }
+
+        if terms.is_empty() {
+            return Err(match self.next()? {
+                Some(token) => unexpected(&token),
+                None => self.unexpected_end("a formula unit"),
+            });
+        }
+        Ok(terms)
+    }
+
+    /// open , term , { term } , [ charge ] , close , [ number ]
+    fn group(&mut self) -> Result<Group, ParseError> {
+        let Some(Tokens::Paren { kind, meta, .. }) = self.next()? else {
+            unreachable!()
+        };
+        // running out of input inside the brackets means the close is missing
+        let unclosed = |error| match error {
+            ParseError::UnexpectedEnd { .. } => ParseError::UnbalancedParen { span: meta.span() },
+            error => error,
+        };
+        let terms = self.terms().map_err(unclosed)?;
+        let charge = self.charge().map_err(unclosed)?;
+        match self.next()? {
+            Some(Tokens::Paren {
+                data: ParenType::CLOSE,
+                kind: close,
+                ..
+            }) if close == kind => {
+                let group = Group::new(self.number()?, terms, charge, kind).unwrap();
+                Ok(group.with_span(self.span(meta.loc())))
             }
-            Some(Ok(Tokens::Paren {
-                data: super::token_types::ParenType::CLOSE,
-                meta,
-            })) => {
-                if paren_level == 0 {
-                    return Err("Invalid closing paren".to_owned());
-                };
-                paren_level -= 1;
-                let group = *current_stack.pop().unwrap();
-                let mut maybe_fu_or_group = current_stack.pop();
-
-                match maybe_fu_or_group {
-                    Some(box Node::Group(_, ref mut vec)) => {
-                        if let Some(Ok(Tokens::Number { data, meta: _ })) = stream.peek() {
-                            if let Node::Group(_, inner_vec) = group {
-                                vec.push(Node::Group(*data, inner_vec));
-                            }
-                        }
-                        current_stack.push(maybe_fu_or_group.unwrap());
-                    }
-                    Some(box Node::ForumulaUnit(_, ref mut vec)) => {
-                        vec.push(group);
-                        current_stack.push(maybe_fu_or_group.unwrap());
-                    }
-
-                    Some(_) => return Err("Invalid Parent".to_owned()),
-                    None => return Err("Stack Underflow in group".to_owned()),
-                }
+            Some(Tokens::Paren {
+                data: ParenType::CLOSE,
+                meta: close,
+                ..
+            }) => Err(ParseError::MismatchedBracket {
+                expected: kind.close(),
+                span: close.span(),
+            }),
+            _ => Err(ParseError::UnbalancedParen { span: meta.span() }),
+        }
+    }
+
+    /// An optional coefficient or subscript, which is 1 if left out
+    fn number(&mut self) -> Result<u16, ParseError> {
+        match self.peek()? {
+            Some(Tokens::Number { data, .. }) => {
+                let number = *data;
+                self.next()?;
+                Ok(number)
             }
-            None => {
-                let fu = *current_stack.pop().unwrap();
-                let mut products_or_none = current_stack.pop();
-                match products_or_none {
-                    Some(box Node::Products(ref mut vec)) => {
-                        let reactants = current_stack.pop().unwrap();
-                        vec.push(fu);
-                        current_stack.push(Box::new(Node::Equation(
-                            reactants,
-                            products_or_none.unwrap(),
-                        )));
-                    }
-                    Some(_) => return Err("Stack had unexpected value".to_owned()),
-                    None => {
-                        current_stack.push(Box::new(fu));
-                    }
-                }
-                break;
+            _ => Ok(1),
+        }
+    }
+
+    /// An optional charge, which is 0 if left out
+    fn charge(&mut self) -> Result<i16, ParseError> {
+        match self.peek()? {
+            Some(Tokens::Charge { data, .. }) => {
+                let charge = *data;
+                self.next()?;
+                Ok(charge)
             }
+            _ => Ok(0),
         }
     }
-    Ok(current_stack.pop().unwrap())
+
+    /// The start of the next token, where a node beginning with it starts
+    fn start(&mut self) -> Result<usize, ParseError> {
+        Ok(match self.peek()? {
+            Some(token) => token.meta().loc(),
+            None => self.end,
+        })
+    }
+
+    /// The span from `start` to the end of the last token taken
+    fn span(&self, start: usize) -> Span {
+        Span::new(start, self.end)
+    }
+
+    fn peek(&mut self) -> Result<Option<&Tokens>, ParseError> {
+        if let Some(Err(_)) = self.stream.peek() {
+            return Err(self.stream.next().unwrap().unwrap_err());
+        }
+        Ok(self.stream.peek().map(|token| token.as_ref().unwrap()))
+    }
+
+    fn next(&mut self) -> Result<Option<Tokens>, ParseError> {
+        let token = self.stream.next().transpose()?;
+        // conditions come after their arrow but are inside it
+        if let Some(token) = &token {
+            self.end = self.end.max(token.meta().span().end);
+        }
+        Ok(token)
+    }
+
+    fn end_of_input(&mut self) -> Result<(), ParseError> {
+        match self.next()? {
+            None => Ok(()),
+            Some(token) => Err(unexpected(&token)),
+        }
+    }
+
+    fn unexpected_end(&self, expected: &str) -> ParseError {
+        ParseError::UnexpectedEnd {
+            expected: expected.to_owned(),
+            span: Span::new(self.end, self.end),
+        }
+    }
+}
+
+/// A side spanning all of its formula units, of which there is at least one
+fn side(units: Vec<FormulaUnit>) -> Side {
+    let span = units[0].span().to(units[units.len() - 1].span());
+    Side::new(units).unwrap().with_span(span)
+}
+
+/// A condition of an arrow starting at `loc`, a species if it reads as a formula unit
+/// without a coefficient, markup if it has a LaTeX command e.g. `\Delta`, otherwise text e.g. 450C
+fn condition(text: &str, loc: usize) -> Condition {
+    let stream = LazyTokenStream::new(text).map(|token| {
+        token.map(|mut token| {
+            let meta = token.meta();
+            *token.meta_mut() = TokenMetadata::new(&meta.raw().clone(), meta.loc() + loc);
+            token
+        })
+    });
+    match parse_formula(stream) {
+        Ok(unit) if !text.starts_with(|val: char| val.is_ascii_digit()) => Condition::Species(unit),
+        _ if is_markup(text) => {
+            Condition::Markup(text.to_owned(), Span::new(loc, loc + text.len()))
+        }
+        _ => Condition::Text(text.to_owned(), Span::new(loc, loc + text.len())),
+    }
+}
+
+/// Whether text has a LaTeX command in it, a backslash followed by a letter
+fn is_markup(text: &str) -> bool {
+    text.as_bytes()
+        .windows(2)
+        .any(|pair| pair[0] == b'\\' && pair[1].is_ascii_alphabetic())
+}
+
+fn unexpected(token: &Tokens) -> ParseError {
+    match token {
+        Tokens::Paren {
+            data: ParenType::CLOSE,
+            meta,
+            ..
+        } => ParseError::UnbalancedParen { span: meta.span() },
+        _ => ParseError::UnexpectedToken {
+            found: token.meta().raw().clone(),
+            span: token.meta().span(),
+        },
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::token_types::TokenMetadata;
+    use crate::{
+        ast_types::{Arrow, BracketKind, Condition, Direction, State},
+        lexer::LazyTokenStream,
+        token_types::TokenMetadata,
+    };
 
     use super::*;
     #[test]
@@ -171,6 +391,7 @@ mod tests {
                 meta: TokenMetadata::new("3", 8),
             },
             Tokens::Yields {
+                data: Arrow::Forward,
                 meta: TokenMetadata::new("->", 9),
             },
             Tokens::Number {
@@ -198,34 +419,24 @@ mod tests {
             },
         ];
 
-        let exp = Node::Equation(
-            Box::new(Node::Reactants(vec![
-                Node::ForumulaUnit(2, vec![Node::Element(1, "Fe".to_owned())]),
-                Node::ForumulaUnit(
-                    1,
-                    vec![
-                        Node::Element(2, "Na".to_owned()),
-                        Node::Element(3, "F".to_owned()),
-                    ],
-                ),
-            ])),
-            Box::new(Node::Products(vec![
-                Node::ForumulaUnit(
-                    2,
-                    vec![
-                        Node::Element(1, "Fe".to_owned()),
-                        Node::Element(1, "Na".to_owned()),
-                    ],
-                ),
-                Node::ForumulaUnit(1, vec![Node::Element(3, "F".to_owned())]),
-            ])),
+        let exp = Equation::new(
+            Side::new(vec![
+                FormulaUnit::new(2, vec![element(1, "Fe")], 0, None).unwrap(),
+                FormulaUnit::new(1, vec![element(2, "Na"), element(3, "F")], 0, None).unwrap(),
+            ])
+            .unwrap(),
+            Side::new(vec![
+                FormulaUnit::new(2, vec![element(1, "Fe"), element(1, "Na")], 0, None).unwrap(),
+                FormulaUnit::new(1, vec![element(3, "F")], 0, None).unwrap(),
+            ])
+            .unwrap(),
         );
 
-        let res = parse(stream.into_iter().map(|box_tokens| Ok(box_tokens)));
+        let res = parse(stream.into_iter().map(Ok));
 
         assert!(res.is_ok());
 
-        assert_eq!(exp, *res.unwrap());
+        assert_eq!(Node::Equation(exp), res.unwrap());
     }
     #[test]
     fn can_parse_formula_unit() {
@@ -252,19 +463,715 @@ mod tests {
             },
         ];
 
-        let exp = Node::ForumulaUnit(
+        let exp = FormulaUnit::new(
+            2,
+            vec![element(1, "Fe"), element(1, "C"), element(3, "O")],
+            0,
+            None,
+        )
+        .unwrap();
+
+        let res = parse(stream.into_iter().map(Ok));
+
+        assert!(res.is_ok());
+
+        assert_eq!(Node::Formula(exp), res.unwrap());
+    }
+
+    #[test]
+    fn can_parse_charge() {
+        let stream = vec![
+            Tokens::Element {
+                data: "S".to_owned(),
+                meta: TokenMetadata::new("S", 0),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 1),
+            },
+            Tokens::Number {
+                data: 4,
+                meta: TokenMetadata::new("4", 2),
+            },
+            Tokens::Charge {
+                data: -2,
+                meta: TokenMetadata::new("^2-", 3),
+            },
+        ];
+
+        let exp = FormulaUnit::new(1, vec![element(1, "S"), element(4, "O")], -2, None).unwrap();
+
+        let res = parse_formula(stream.into_iter().map(Ok));
+
+        assert_eq!(Ok(exp), res);
+    }
+
+    #[test]
+    fn rejects_charge_before_elements() {
+        let stream = vec![
+            Tokens::Element {
+                data: "Fe".to_owned(),
+                meta: TokenMetadata::new("Fe", 0),
+            },
+            Tokens::Charge {
+                data: 3,
+                meta: TokenMetadata::new("^3+", 2),
+            },
+            Tokens::Element {
+                data: "O".to_owned(),
+                meta: TokenMetadata::new("O", 5),
+            },
+        ];
+
+        let res = parse(stream.into_iter().map(Ok));
+
+        assert_eq!(
+            Err(ParseError::UnexpectedToken {
+                found: "O".to_owned(),
+                span: Span::new(5, 6)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn can_parse_states() {
+        let stream = vec![
+            Tokens::Element {
+                data: "Na".to_owned(),
+                meta: TokenMetadata::new("Na", 0),
+            },
+            Tokens::Element {
+                data: "Cl".to_owned(),
+                meta: TokenMetadata::new("Cl", 2),
+            },
+            Tokens::State {
+                data: State::Aqueous,
+                meta: TokenMetadata::new("(aq)", 4),
+            },
+        ];
+
+        let exp = FormulaUnit::new(
+            1,
+            vec![element(1, "Na"), element(1, "Cl")],
+            0,
+            Some(State::Aqueous),
+        )
+        .unwrap();
+
+        let res = parse_formula(stream.into_iter().map(Ok));
+
+        assert_eq!(Ok(exp), res);
+    }
+
+    #[test]
+    fn rejects_state_before_elements() {
+        let stream = vec![
+            Tokens::Element {
+                data: "Na".to_owned(),
+                meta: TokenMetadata::new("Na", 0),
+            },
+            Tokens::State {
+                data: State::Solid,
+                meta: TokenMetadata::new("(s)", 2),
+            },
+            Tokens::Element {
+                data: "Cl".to_owned(),
+                meta: TokenMetadata::new("Cl", 5),
+            },
+        ];
+
+        let res = parse(stream.into_iter().map(Ok));
+
+        assert_eq!(
+            Err(ParseError::UnexpectedToken {
+                found: "Cl".to_owned(),
+                span: Span::new(5, 7)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn errors_have_spans() {
+        let cases = [
+            (
+                "H2)",
+                ParseError::UnbalancedParen {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "H2->",
+                ParseError::UnexpectedEnd {
+                    expected: "a formula unit".to_owned(),
+                    span: Span::new(4, 4),
+                },
+            ),
+            (
+                "H2+O2->H2O2->H2",
+                ParseError::UnexpectedToken {
+                    found: "->".to_owned(),
+                    span: Span::new(11, 13),
+                },
+            ),
+        ];
+
+        for (to_parse, exp) in cases {
+            let res = parse(LazyTokenStream::new(to_parse));
+            assert_eq!(Err(exp), res);
+        }
+    }
+
+    #[test]
+    fn records_the_arrow() {
+        let cases = [
+            ("N2+3H2->2NH3", Arrow::Forward, Direction::Forward),
+            ("2NH3<-N2+3H2", Arrow::Reverse, Direction::Reverse),
+            ("N2+3H2<->2NH3", Arrow::Reversible, Direction::Equilibrium),
+            (
+                "N2 + 3H2 ⇌ 2NH3",
+                Arrow::Equilibrium,
+                Direction::Equilibrium,
+            ),
+            ("N2+3H2=2NH3", Arrow::Equals, Direction::Forward),
+        ];
+        for (source, arrow, direction) in cases {
+            let equation = equation_str(source).unwrap();
+            assert_eq!(arrow, equation.arrow(), "parsing {source}");
+            assert_eq!(direction, equation.direction(), "parsing {source}");
+        }
+        assert_ne!(equation_str("N2+3H2->2NH3"), equation_str("N2+3H2<=>2NH3"));
+    }
+
+    #[test]
+    fn can_parse_conditions() {
+        let equation = equation_str("2KClO3 -[MnO2, 1.5 atm][heat]-> 2KCl + 3O2").unwrap();
+        let catalyst =
+            FormulaUnit::new(1, vec![element(1, "Mn"), element(2, "O")], 0, None).unwrap();
+        assert_eq!(
+            &[
+                Condition::Species(catalyst),
+                Condition::Text("1.5 atm".to_owned(), Span::default()),
+            ],
+            equation.above()
+        );
+        assert_eq!(
+            &[Condition::Text("heat".to_owned(), Span::default())],
+            equation.below()
+        );
+        assert_eq!(Span::new(9, 13), equation.above()[0].span());
+        assert_eq!(Span::new(15, 22), equation.above()[1].span());
+        assert_eq!(Span::new(24, 28), equation.below()[0].span());
+
+        // a number is not a coefficient
+        let equation = equation_str("N2+3H2<=[450C]=>2NH3").unwrap();
+        assert_eq!(Arrow::Equilibrium, equation.arrow());
+        assert_eq!(
+            &[Condition::Text("450C".to_owned(), Span::default())],
+            equation.above()
+        );
+
+        // a LaTeX command is markup, a lone backslash is not
+        let equation = equation_str(r"A-[\Delta][a \ b]->B").unwrap();
+        assert_eq!(
+            &[Condition::Markup(r"\Delta".to_owned(), Span::default())],
+            equation.above()
+        );
+        assert_eq!(
+            &[Condition::Text(r"a \ b".to_owned(), Span::default())],
+            equation.below()
+        );
+
+        assert_ne!(
+            equation_str("N2+3H2->2NH3"),
+            equation_str("N2+3H2-[Fe]->2NH3")
+        );
+        assert_eq!(
+            Err(ParseError::UnexpectedEnd {
+                expected: "a formula unit".to_owned(),
+                span: Span::new(10, 10)
+            }),
+            equation_str("N2 -[Fe]-> ")
+        );
+    }
+
+    #[test]
+    fn can_parse_half_reactions() {
+        let equation = equation_str("Fe^3+ + e- -> Fe^2+").unwrap();
+        let exp = Equation::new(
+            Side::new(vec![
+                FormulaUnit::new(1, vec![element(1, "Fe")], 3, None).unwrap(),
+                FormulaUnit::electron(1),
+            ])
+            .unwrap(),
+            Side::new(vec![
+                FormulaUnit::new(1, vec![element(1, "Fe")], 2, None).unwrap()
+            ])
+            .unwrap(),
+        );
+        assert_eq!(exp, equation);
+
+        let electrons = &equation.reactants().units()[1];
+        assert!(electrons.is_electron());
+        assert!(!equation.reactants().units()[0].is_electron());
+        assert!(electrons.parts().is_empty());
+        assert_eq!(Span::new(8, 10), electrons.span());
+        let Species::Electron(electron) = electrons.species() else {
+            panic!("Expected an electron");
+        };
+        assert_eq!(Span::new(8, 10), electron.span());
+
+        let unit = formula_str("2e⁻").unwrap();
+        assert_eq!(FormulaUnit::electron(2), unit);
+        assert_eq!(Span::new(0, 5), unit.span());
+    }
+
+    #[test]
+    fn spacing_around_plus_does_not_matter() {
+        let exp = equation_str("2H2+O2->2H2O");
+        assert!(exp.is_ok());
+        for source in ["2H2 + O2 -> 2H2O", "2H2+ O2 -> 2H2O", "2H2 +O2 -> 2H2O"] {
+            assert_eq!(exp, equation_str(source), "parsing {source}");
+        }
+    }
+
+    fn parse_str(to_parse: &str) -> Result<Node, ParseError> {
+        parse(LazyTokenStream::new(to_parse))
+    }
+
+    fn formula_str(to_parse: &str) -> Result<FormulaUnit, ParseError> {
+        parse_formula(LazyTokenStream::new(to_parse))
+    }
+
+    fn equation_str(to_parse: &str) -> Result<Equation, ParseError> {
+        parse_equation(LazyTokenStream::new(to_parse))
+    }
+
+    fn element(count: u16, symbol: &str) -> Term {
+        Term::Element(ElementCount::new(symbol, count))
+    }
+
+    fn group(subscript: u16, terms: Vec<Term>, charge: i16, kind: BracketKind) -> Term {
+        Term::Group(Group::new(subscript, terms, charge, kind).unwrap())
+    }
+
+    #[test]
+    fn can_parse_group_with_subscript() {
+        let exp = FormulaUnit::new(
+            1,
+            vec![
+                element(1, "Ca"),
+                group(
+                    2,
+                    vec![element(1, "O"), element(1, "H")],
+                    0,
+                    BracketKind::Round,
+                ),
+            ],
+            0,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp), formula_str("Ca(OH)2"));
+    }
+
+    #[test]
+    fn can_parse_group_first() {
+        let exp = FormulaUnit::new(
+            3,
+            vec![
+                group(
+                    2,
+                    vec![element(1, "N"), element(4, "H")],
+                    0,
+                    BracketKind::Round,
+                ),
+                element(1, "S"),
+                element(4, "O"),
+            ],
+            0,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp), formula_str("3(NH4)2SO4"));
+    }
+
+    #[test]
+    fn can_parse_nested_groups_without_subscripts() {
+        let exp = FormulaUnit::new(
+            1,
+            vec![
+                element(4, "K"),
+                group(
+                    1,
+                    vec![
+                        element(1, "Fe"),
+                        group(
+                            6,
+                            vec![element(1, "C"), element(1, "N")],
+                            0,
+                            BracketKind::Round,
+                        ),
+                    ],
+                    0,
+                    BracketKind::Round,
+                ),
+            ],
+            0,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp), formula_str("K4(Fe(CN)6)"));
+    }
+
+    #[test]
+    fn can_parse_deeply_nested_groups() {
+        let exp = FormulaUnit::new(
+            1,
+            vec![
+                group(
+                    2,
+                    vec![
+                        group(
+                            3,
+                            vec![element(1, "C"), element(3, "H")],
+                            0,
+                            BracketKind::Round,
+                        ),
+                        element(1, "C"),
+                    ],
+                    0,
+                    BracketKind::Round,
+                ),
+                element(1, "O"),
+            ],
+            0,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp), formula_str("((CH3)3C)2O"));
+    }
+
+    #[test]
+    fn can_parse_group_charges() {
+        let exp = FormulaUnit::new(
+            1,
+            vec![
+                element(1, "Ca"),
+                group(
+                    2,
+                    vec![element(1, "N"), element(3, "O")],
+                    -1,
+                    BracketKind::Round,
+                ),
+            ],
+            2,
+            Some(State::Aqueous),
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp), formula_str("Ca(NO3^-)2^2+(aq)"));
+    }
+
+    #[test]
+    fn can_parse_single_reactant() {
+        let exp = Equation::new(
+            Side::new(vec![FormulaUnit::new(
+                2,
+                vec![element(2, "H"), element(1, "O")],
+                0,
+                None,
+            )
+            .unwrap()])
+            .unwrap(),
+            Side::new(vec![
+                FormulaUnit::new(2, vec![element(2, "H")], 0, None).unwrap(),
+                FormulaUnit::new(1, vec![element(2, "O")], 0, None).unwrap(),
+            ])
+            .unwrap(),
+        );
+
+        assert_eq!(Ok(exp), equation_str("2H2O -> 2H2 + O2"));
+    }
+
+    #[test]
+    fn can_parse_equation_with_groups() {
+        let exp = Equation::new(
+            Side::new(vec![
+                FormulaUnit::new(
+                    1,
+                    vec![
+                        element(1, "Ca"),
+                        group(
+                            2,
+                            vec![element(1, "O"), element(1, "H")],
+                            0,
+                            BracketKind::Round,
+                        ),
+                    ],
+                    0,
+                    None,
+                )
+                .unwrap(),
+                FormulaUnit::new(
+                    2,
+                    vec![element(1, "H"), element(1, "N"), element(3, "O")],
+                    0,
+                    None,
+                )
+                .unwrap(),
+            ])
+            .unwrap(),
+            Side::new(vec![
+                FormulaUnit::new(
+                    1,
+                    vec![
+                        element(1, "Ca"),
+                        group(
+                            2,
+                            vec![element(1, "N"), element(3, "O")],
+                            0,
+                            BracketKind::Round,
+                        ),
+                    ],
+                    0,
+                    None,
+                )
+                .unwrap(),
+                FormulaUnit::new(2, vec![element(2, "H"), element(1, "O")], 0, None).unwrap(),
+            ])
+            .unwrap(),
+        );
+
+        assert_eq!(Ok(exp), equation_str("Ca(OH)2+2HNO3->Ca(NO3)2+2H2O"));
+    }
+
+    #[test]
+    fn can_parse_square_and_curly_brackets() {
+        let exp = FormulaUnit::new(
+            1,
+            vec![
+                group(
+                    1,
+                    vec![
+                        element(1, "Cu"),
+                        group(
+                            4,
+                            vec![element(1, "N"), element(3, "H")],
+                            0,
+                            BracketKind::Curly,
+                        ),
+                    ],
+                    0,
+                    BracketKind::Square,
+                ),
+                element(1, "S"),
+                element(4, "O"),
+            ],
+            0,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp), formula_str("[Cu{NH3}4]SO4"));
+    }
+
+    #[test]
+    fn can_parse_hydrates() {
+        let exp = FormulaUnit::adduct(
             2,
             vec![
-                Node::Element(1, "Fe".to_owned()),
-                Node::Element(1, "C".to_owned()),
-                Node::Element(3, "O".to_owned()),
+                Part::new(
+                    1,
+                    vec![element(1, "Cu"), element(1, "S"), element(4, "O")],
+                    0,
+                )
+                .unwrap(),
+                Part::new(5, vec![element(2, "H"), element(1, "O")], 0).unwrap(),
             ],
+            Some(State::Solid),
+        )
+        .unwrap();
+
+        assert_eq!(Ok(exp.clone()), formula_str("2CuSO4·5H2O(s)"));
+        assert_eq!(Ok(exp), formula_str("2CuSO4*5H2O(s)"));
+    }
+
+    #[test]
+    fn entry_points_expect_their_type() {
+        let unit = FormulaUnit::new(2, vec![element(2, "H"), element(1, "O")], 0, None).unwrap();
+        assert_eq!(Ok(Node::Formula(unit.clone())), parse_str("2H2O"));
+        assert_eq!(Ok(unit), formula_str("2H2O"));
+
+        assert_eq!(
+            Err(ParseError::UnexpectedToken {
+                found: "+".to_owned(),
+                span: Span::new(3, 4),
+            }),
+            formula_str("2H2+O2")
+        );
+        assert_eq!(
+            Err(ParseError::UnexpectedEnd {
+                expected: "an arrow".to_owned(),
+                span: Span::new(4, 4),
+            }),
+            equation_str("2H2O")
         );
+    }
 
-        let res = parse(stream.into_iter().map(|box_tokens| Ok(box_tokens)));
+    #[test]
+    fn can_parse_pasted_unicode() {
+        let cases = [
+            ("H₂SO₄", "H2SO4"),
+            ("2H₂ + O₂ → 2H₂O", "2H2+O2->2H2O"),
+            ("CuSO₄·5H₂O", "CuSO4*5H2O"),
+            ("Fe³⁺ + 3OH⁻ ⟶ Fe(OH)₃", "Fe^3++3OH^-->Fe(OH)3"),
+        ];
+        for (unicode, ascii) in cases {
+            assert_eq!(parse_str(ascii), parse_str(unicode), "parsing {unicode}");
+        }
 
-        assert!(res.is_ok());
+        let equation = equation_str("2H₂ + O₂ → 2H₂O").unwrap();
+        assert_eq!(Span::new(0, 12), equation.reactants().span());
+        assert_eq!(Span::new(17, 23), equation.products().span());
+    }
+
+    #[test]
+    fn nodes_have_spans() {
+        let source = "2H2O + Ca(OH)2\n-> CuSO4·5H2O(s)";
+        let equation = equation_str(source).unwrap();
+        assert_eq!(Span::new(0, source.len()), equation.span());
+
+        let reactants = equation.reactants();
+        assert_eq!(Span::new(0, 14), reactants.span());
+        assert_eq!(Span::new(0, 4), reactants.units()[0].span());
+
+        let calcium_hydroxide = &reactants.units()[1];
+        let terms = calcium_hydroxide.parts()[0].terms();
+        assert_eq!(Span::new(7, 14), calcium_hydroxide.span());
+        let (Term::Element(calcium), Term::Group(hydroxide)) = (&terms[0], &terms[1]) else {
+            panic!("Expected an element and a group");
+        };
+        assert_eq!(Span::new(7, 9), calcium.span());
+        assert_eq!(Span::new(9, 14), hydroxide.span());
+
+        let hydrate = &equation.products().units()[0];
+        assert_eq!(Span::new(18, 32), hydrate.span());
+        assert_eq!(Span::new(18, 23), hydrate.parts()[0].span());
+        assert_eq!(Span::new(25, 29), hydrate.parts()[1].span());
+        assert_eq!((2, 4), hydrate.span().line_column(source));
+    }
+
+    #[test]
+    fn constructors_reject_empty_input() {
+        assert_eq!(None, Side::new(vec![]));
+        assert_eq!(None, FormulaUnit::new(1, vec![], 0, None));
+        assert_eq!(None, FormulaUnit::adduct(1, vec![], None));
+        assert_eq!(
+            None,
+            FormulaUnit::from_species(1, Species::Formula(vec![]), None)
+        );
+        assert_eq!(None, Part::new(1, vec![], 0));
+        assert_eq!(None, Group::new(1, vec![], 0, BracketKind::Round));
+    }
 
-        assert_eq!(exp, *res.unwrap());
+    #[test]
+    fn rejects_malformed_input() {
+        let cases = [
+            (
+                "Ca(OH",
+                ParseError::UnbalancedParen {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "Ca(OH->H2O",
+                ParseError::UnbalancedParen {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "H2O(",
+                ParseError::UnbalancedParen {
+                    span: Span::new(3, 4),
+                },
+            ),
+            (
+                "Fe(",
+                ParseError::UnbalancedParen {
+                    span: Span::new(2, 3),
+                },
+            ),
+            (
+                "Ca()",
+                ParseError::UnbalancedParen {
+                    span: Span::new(3, 4),
+                },
+            ),
+            (
+                "H2+O2",
+                ParseError::UnexpectedEnd {
+                    expected: "an arrow".to_owned(),
+                    span: Span::new(5, 5),
+                },
+            ),
+            (
+                "->H2",
+                ParseError::UnexpectedToken {
+                    found: "->".to_owned(),
+                    span: Span::new(0, 2),
+                },
+            ),
+            (
+                "2",
+                ParseError::UnexpectedEnd {
+                    expected: "a formula unit".to_owned(),
+                    span: Span::new(1, 1),
+                },
+            ),
+            (
+                "H2 + + O2",
+                ParseError::UnexpectedToken {
+                    found: "+".to_owned(),
+                    span: Span::new(5, 6),
+                },
+            ),
+            (
+                "",
+                ParseError::UnexpectedEnd {
+                    expected: "a formula unit".to_owned(),
+                    span: Span::new(0, 0),
+                },
+            ),
+            (
+                "Ca(OH]2",
+                ParseError::MismatchedBracket {
+                    expected: ')',
+                    span: Span::new(5, 6),
+                },
+            ),
+            (
+                "CuSO4·",
+                ParseError::UnexpectedEnd {
+                    expected: "a formula unit".to_owned(),
+                    span: Span::new(7, 7),
+                },
+            ),
+            (
+                "K3[Fe(CN)6",
+                ParseError::UnbalancedParen {
+                    span: Span::new(2, 3),
+                },
+            ),
+        ];
+
+        for (to_parse, exp) in cases {
+            assert_eq!(Err(exp), parse_str(to_parse), "parsing {to_parse}");
+        }
     }
 }
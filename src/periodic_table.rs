@@ -0,0 +1,320 @@
+use crate::{
+    ast_types::{Condition, Node, Term},
+    error::ParseError,
+    token_types::Tokens,
+};
+
+/// The block of the periodic table an element belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Block {
+    S,
+    P,
+    D,
+    F,
+}
+
+/// An entry in the periodic table
+#[derive(Debug, PartialEq)]
+pub struct Element {
+    /// The element symbol e.g. "Fe"
+    pub symbol: &'static str,
+    /// The english name e.g. "Iron"
+    pub name: &'static str,
+    /// The number of protons e.g. 26
+    pub atomic_number: u8,
+    /// The standard atomic weight in g/mol.
+    /// For elements without stable isotopes this is the mass number of the most stable isotope
+    pub atomic_weight: f64,
+    /// The IUPAC group (1-18), `None` for the lanthanides and actinides
+    pub group: Option<u8>,
+    /// The period (row) e.g. 4
+    pub period: u8,
+    /// The block e.g. `Block::D`
+    pub block: Block,
+}
+
+const fn el(
+    atomic_number: u8,
+    symbol: &'static str,
+    name: &'static str,
+    atomic_weight: f64,
+    group: Option<u8>,
+    period: u8,
+    block: Block,
+) -> Element {
+    Element {
+        symbol,
+        name,
+        atomic_number,
+        atomic_weight,
+        group,
+        period,
+        block,
+    }
+}
+
+/// All 118 elements, ordered by atomic number
+pub static ELEMENTS: [Element; 118] = [
+    el(1, "H", "Hydrogen", 1.008, Some(1), 1, Block::S),
+    el(2, "He", "Helium", 4.0026, Some(18), 1, Block::S),
+    el(3, "Li", "Lithium", 6.94, Some(1), 2, Block::S),
+    el(4, "Be", "Beryllium", 9.0122, Some(2), 2, Block::S),
+    el(5, "B", "Boron", 10.81, Some(13), 2, Block::P),
+    el(6, "C", "Carbon", 12.011, Some(14), 2, Block::P),
+    el(7, "N", "Nitrogen", 14.007, Some(15), 2, Block::P),
+    el(8, "O", "Oxygen", 15.999, Some(16), 2, Block::P),
+    el(9, "F", "Fluorine", 18.998, Some(17), 2, Block::P),
+    el(10, "Ne", "Neon", 20.180, Some(18), 2, Block::P),
+    el(11, "Na", "Sodium", 22.990, Some(1), 3, Block::S),
+    el(12, "Mg", "Magnesium", 24.305, Some(2), 3, Block::S),
+    el(13, "Al", "Aluminium", 26.982, Some(13), 3, Block::P),
+    el(14, "Si", "Silicon", 28.085, Some(14), 3, Block::P),
+    el(15, "P", "Phosphorus", 30.974, Some(15), 3, Block::P),
+    el(16, "S", "Sulfur", 32.06, Some(16), 3, Block::P),
+    el(17, "Cl", "Chlorine", 35.45, Some(17), 3, Block::P),
+    el(18, "Ar", "Argon", 39.948, Some(18), 3, Block::P),
+    el(19, "K", "Potassium", 39.098, Some(1), 4, Block::S),
+    el(20, "Ca", "Calcium", 40.078, Some(2), 4, Block::S),
+    el(21, "Sc", "Scandium", 44.956, Some(3), 4, Block::D),
+    el(22, "Ti", "Titanium", 47.867, Some(4), 4, Block::D),
+    el(23, "V", "Vanadium", 50.942, Some(5), 4, Block::D),
+    el(24, "Cr", "Chromium", 51.996, Some(6), 4, Block::D),
+    el(25, "Mn", "Manganese", 54.938, Some(7), 4, Block::D),
+    el(26, "Fe", "Iron", 55.845, Some(8), 4, Block::D),
+    el(27, "Co", "Cobalt", 58.933, Some(9), 4, Block::D),
+    el(28, "Ni", "Nickel", 58.693, Some(10), 4, Block::D),
+    el(29, "Cu", "Copper", 63.546, Some(11), 4, Block::D),
+    el(30, "Zn", "Zinc", 65.38, Some(12), 4, Block::D),
+    el(31, "Ga", "Gallium", 69.723, Some(13), 4, Block::P),
+    el(32, "Ge", "Germanium", 72.630, Some(14), 4, Block::P),
+    el(33, "As", "Arsenic", 74.922, Some(15), 4, Block::P),
+    el(34, "Se", "Selenium", 78.971, Some(16), 4, Block::P),
+    el(35, "Br", "Bromine", 79.904, Some(17), 4, Block::P),
+    el(36, "Kr", "Krypton", 83.798, Some(18), 4, Block::P),
+    el(37, "Rb", "Rubidium", 85.468, Some(1), 5, Block::S),
+    el(38, "Sr", "Strontium", 87.62, Some(2), 5, Block::S),
+    el(39, "Y", "Yttrium", 88.906, Some(3), 5, Block::D),
+    el(40, "Zr", "Zirconium", 91.224, Some(4), 5, Block::D),
+    el(41, "Nb", "Niobium", 92.906, Some(5), 5, Block::D),
+    el(42, "Mo", "Molybdenum", 95.95, Some(6), 5, Block::D),
+    el(43, "Tc", "Technetium", 98.0, Some(7), 5, Block::D),
+    el(44, "Ru", "Ruthenium", 101.07, Some(8), 5, Block::D),
+    el(45, "Rh", "Rhodium", 102.91, Some(9), 5, Block::D),
+    el(46, "Pd", "Palladium", 106.42, Some(10), 5, Block::D),
+    el(47, "Ag", "Silver", 107.87, Some(11), 5, Block::D),
+    el(48, "Cd", "Cadmium", 112.41, Some(12), 5, Block::D),
+    el(49, "In", "Indium", 114.82, Some(13), 5, Block::P),
+    el(50, "Sn", "Tin", 118.71, Some(14), 5, Block::P),
+    el(51, "Sb", "Antimony", 121.76, Some(15), 5, Block::P),
+    el(52, "Te", "Tellurium", 127.60, Some(16), 5, Block::P),
+    el(53, "I", "Iodine", 126.90, Some(17), 5, Block::P),
+    el(54, "Xe", "Xenon", 131.29, Some(18), 5, Block::P),
+    el(55, "Cs", "Caesium", 132.91, Some(1), 6, Block::S),
+    el(56, "Ba", "Barium", 137.33, Some(2), 6, Block::S),
+    el(57, "La", "Lanthanum", 138.91, None, 6, Block::F),
+    el(58, "Ce", "Cerium", 140.12, None, 6, Block::F),
+    el(59, "Pr", "Praseodymium", 140.91, None, 6, Block::F),
+    el(60, "Nd", "Neodymium", 144.24, None, 6, Block::F),
+    el(61, "Pm", "Promethium", 145.0, None, 6, Block::F),
+    el(62, "Sm", "Samarium", 150.36, None, 6, Block::F),
+    el(63, "Eu", "Europium", 151.96, None, 6, Block::F),
+    el(64, "Gd", "Gadolinium", 157.25, None, 6, Block::F),
+    el(65, "Tb", "Terbium", 158.93, None, 6, Block::F),
+    el(66, "Dy", "Dysprosium", 162.50, None, 6, Block::F),
+    el(67, "Ho", "Holmium", 164.93, None, 6, Block::F),
+    el(68, "Er", "Erbium", 167.26, None, 6, Block::F),
+    el(69, "Tm", "Thulium", 168.93, None, 6, Block::F),
+    el(70, "Yb", "Ytterbium", 173.05, None, 6, Block::F),
+    el(71, "Lu", "Lutetium", 174.97, Some(3), 6, Block::D),
+    el(72, "Hf", "Hafnium", 178.49, Some(4), 6, Block::D),
+    el(73, "Ta", "Tantalum", 180.95, Some(5), 6, Block::D),
+    el(74, "W", "Tungsten", 183.84, Some(6), 6, Block::D),
+    el(75, "Re", "Rhenium", 186.21, Some(7), 6, Block::D),
+    el(76, "Os", "Osmium", 190.23, Some(8), 6, Block::D),
+    el(77, "Ir", "Iridium", 192.22, Some(9), 6, Block::D),
+    el(78, "Pt", "Platinum", 195.08, Some(10), 6, Block::D),
+    el(79, "Au", "Gold", 196.97, Some(11), 6, Block::D),
+    el(80, "Hg", "Mercury", 200.59, Some(12), 6, Block::D),
+    el(81, "Tl", "Thallium", 204.38, Some(13), 6, Block::P),
+    el(82, "Pb", "Lead", 207.2, Some(14), 6, Block::P),
+    el(83, "Bi", "Bismuth", 208.98, Some(15), 6, Block::P),
+    el(84, "Po", "Polonium", 209.0, Some(16), 6, Block::P),
+    el(85, "At", "Astatine", 210.0, Some(17), 6, Block::P),
+    el(86, "Rn", "Radon", 222.0, Some(18), 6, Block::P),
+    el(87, "Fr", "Francium", 223.0, Some(1), 7, Block::S),
+    el(88, "Ra", "Radium", 226.0, Some(2), 7, Block::S),
+    el(89, "Ac", "Actinium", 227.0, None, 7, Block::F),
+    el(90, "Th", "Thorium", 232.04, None, 7, Block::F),
+    el(91, "Pa", "Protactinium", 231.04, None, 7, Block::F),
+    el(92, "U", "Uranium", 238.03, None, 7, Block::F),
+    el(93, "Np", "Neptunium", 237.0, None, 7, Block::F),
+    el(94, "Pu", "Plutonium", 244.0, None, 7, Block::F),
+    el(95, "Am", "Americium", 243.0, None, 7, Block::F),
+    el(96, "Cm", "Curium", 247.0, None, 7, Block::F),
+    el(97, "Bk", "Berkelium", 247.0, None, 7, Block::F),
+    el(98, "Cf", "Californium", 251.0, None, 7, Block::F),
+    el(99, "Es", "Einsteinium", 252.0, None, 7, Block::F),
+    el(100, "Fm", "Fermium", 257.0, None, 7, Block::F),
+    el(101, "Md", "Mendelevium", 258.0, None, 7, Block::F),
+    el(102, "No", "Nobelium", 259.0, None, 7, Block::F),
+    el(103, "Lr", "Lawrencium", 266.0, Some(3), 7, Block::D),
+    el(104, "Rf", "Rutherfordium", 267.0, Some(4), 7, Block::D),
+    el(105, "Db", "Dubnium", 268.0, Some(5), 7, Block::D),
+    el(106, "Sg", "Seaborgium", 269.0, Some(6), 7, Block::D),
+    el(107, "Bh", "Bohrium", 270.0, Some(7), 7, Block::D),
+    el(108, "Hs", "Hassium", 269.0, Some(8), 7, Block::D),
+    el(109, "Mt", "Meitnerium", 278.0, Some(9), 7, Block::D),
+    el(110, "Ds", "Darmstadtium", 281.0, Some(10), 7, Block::D),
+    el(111, "Rg", "Roentgenium", 282.0, Some(11), 7, Block::D),
+    el(112, "Cn", "Copernicium", 285.0, Some(12), 7, Block::D),
+    el(113, "Nh", "Nihonium", 286.0, Some(13), 7, Block::P),
+    el(114, "Fl", "Flerovium", 289.0, Some(14), 7, Block::P),
+    el(115, "Mc", "Moscovium", 290.0, Some(15), 7, Block::P),
+    el(116, "Lv", "Livermorium", 293.0, Some(16), 7, Block::P),
+    el(117, "Ts", "Tennessine", 294.0, Some(17), 7, Block::P),
+    el(118, "Og", "Oganesson", 294.0, Some(18), 7, Block::P),
+];
+
+/// Look up an element by its symbol e.g. "Fe"
+pub fn lookup(symbol: &str) -> Option<&'static Element> {
+    ELEMENTS.iter().find(|element| element.symbol == symbol)
+}
+
+/// Look up an element by its atomic number e.g. 26
+pub fn by_atomic_number(atomic_number: u8) -> Option<&'static Element> {
+    ELEMENTS.get((atomic_number as usize).checked_sub(1)?)
+}
+
+/// Wrap a token stream (usually `LazyTokenStream`) so that element symbols
+/// which are not in the periodic table become errors
+pub fn validate<T: Iterator<Item = Result<Tokens, ParseError>>>(
+    stream: T,
+) -> impl Iterator<Item = Result<Tokens, ParseError>> {
+    stream.map(|token| match token {
+        Ok(Tokens::Element { data, meta }) if lookup(&data).is_none() => {
+            Err(ParseError::UnknownElement {
+                symbol: data,
+                span: meta.span(),
+            })
+        }
+        other => other,
+    })
+}
+
+/// Reject element symbols in the species conditions of an equation, e.g. the Xx of `-[Xx]->`.
+/// The parser lexes conditions itself, so `validate` never sees their tokens
+pub(crate) fn validate_conditions(node: &Node) -> Result<(), ParseError> {
+    let Node::Equation(equation) = node else {
+        return Ok(());
+    };
+    for condition in equation.above().iter().chain(equation.below()) {
+        if let Condition::Species(unit) = condition {
+            for part in unit.parts() {
+                validate_terms(part.terms())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_terms(terms: &[Term]) -> Result<(), ParseError> {
+    for term in terms {
+        match term {
+            Term::Element(element) if lookup(element.symbol()).is_none() => {
+                return Err(ParseError::UnknownElement {
+                    symbol: element.symbol().to_owned(),
+                    span: element.span(),
+                });
+            }
+            Term::Group(group) => validate_terms(group.terms())?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Span, lexer::LazyTokenStream, parse_validated};
+
+    #[test]
+    fn table_is_ordered_by_atomic_number() {
+        for (index, element) in ELEMENTS.iter().enumerate() {
+            assert_eq!(index + 1, element.atomic_number as usize);
+        }
+    }
+
+    #[test]
+    fn can_lookup_elements() {
+        let iron = lookup("Fe").unwrap();
+        assert_eq!("Iron", iron.name);
+        assert_eq!(26, iron.atomic_number);
+        assert_eq!(Some(8), iron.group);
+        assert_eq!(4, iron.period);
+        assert_eq!(Block::D, iron.block);
+
+        assert_eq!(Some(iron), by_atomic_number(26));
+        assert_eq!(None, lookup("Xx"));
+        assert_eq!(None, by_atomic_number(0));
+        assert_eq!(None, by_atomic_number(119));
+    }
+
+    #[test]
+    fn validate_rejects_unknown_elements() {
+        let to_parse = &String::from("2Fe+Fx");
+        let res: Result<Vec<Tokens>, ParseError> =
+            validate(LazyTokenStream::new(to_parse)).collect();
+
+        assert_eq!(
+            Err(ParseError::UnknownElement {
+                symbol: "Fx".to_owned(),
+                span: Span::new(4, 6)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn validate_rejects_wrong_case() {
+        let to_parse = &String::from("CL2");
+        let res: Result<Vec<Tokens>, ParseError> =
+            validate(LazyTokenStream::new(to_parse)).collect();
+
+        assert_eq!(
+            Err(ParseError::UnknownElement {
+                symbol: "L".to_owned(),
+                span: Span::new(1, 2)
+            }),
+            res
+        );
+    }
+
+    #[test]
+    fn validate_accepts_known_elements() {
+        let to_parse = &String::from("4Fe+3O2->2Fe2O3");
+        let res: Result<Vec<Tokens>, ParseError> =
+            validate(LazyTokenStream::new(to_parse)).collect();
+
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn validates_species_conditions() {
+        assert_eq!(
+            Err(ParseError::UnknownElement {
+                symbol: "Xx".to_owned(),
+                span: Span::new(5, 7)
+            }),
+            parse_validated(String::from("N2 -[Xx]-> N2"))
+        );
+        assert_eq!(
+            Err(ParseError::UnknownElement {
+                symbol: "Qq".to_owned(),
+                span: Span::new(9, 11)
+            }),
+            parse_validated(String::from("Fe-[Cu][(Qq)2]->Fe"))
+        );
+        assert!(parse_validated(String::from("N2+3H2-[Fe][450C, heat]->2NH3")).is_ok());
+    }
+}
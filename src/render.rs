@@ -0,0 +1,1103 @@
+//! Turning nodes back into text.
+//! Each output format implements `Renderer`, and `render` walks the tree calling it for every piece.
+//! The provided methods of `Renderer` write the plain text that `parse` reads, so a format
+//! only overrides the pieces it writes differently.
+
+use crate::{
+    ast_types::{
+        Arrow, BracketKind, Condition, Equation, FormulaUnit, Group, Node, Part, Side, State, Term,
+    },
+    mhchem::LATEX_ESCAPES,
+    unicode::{digits, SUBSCRIPT_DIGITS, SUPERSCRIPT_DIGITS},
+};
+use std::fmt::{self, Display};
+
+/// Options shared by the text formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Put spaces around plus signs and arrows e.g. `2H2 + O2 -> 2H2O`
+    pub spaced: bool,
+    /// Leave out coefficients, subscripts and counts of 1 e.g. `H2O` rather than `1H2O1`
+    pub omit_ones: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            spaced: false,
+            omit_ones: true,
+        }
+    }
+}
+
+/// Writes each piece of a node in an output format
+pub trait Renderer {
+    fn options(&self) -> FormatOptions {
+        FormatOptions::default()
+    }
+
+    /// The coefficient of a formula unit, or the count of a part after a separator
+    fn coefficient(&self, out: &mut String, coefficient: u16) {
+        out.push_str(&coefficient.to_string());
+    }
+
+    fn element(&self, out: &mut String, symbol: &str) {
+        out.push_str(symbol);
+    }
+
+    /// The subscript of an element or group
+    fn subscript(&self, out: &mut String, subscript: u16) {
+        out.push_str(&subscript.to_string());
+    }
+
+    /// An element or group which has already been written as `base`, followed by its subscript.
+    /// Formats which wrap the two together override this rather than `subscript`
+    fn subscripted(&self, out: &mut String, base: &str, subscript: u16) {
+        out.push_str(base);
+        self.subscript(out, subscript);
+    }
+
+    fn open(&self, out: &mut String, kind: BracketKind) {
+        out.push(kind.open());
+    }
+
+    fn close(&self, out: &mut String, kind: BracketKind) {
+        out.push(kind.close());
+    }
+
+    /// A charge other than 0, e.g. `^2-` or `^+`
+    fn charge(&self, out: &mut String, charge: i16) {
+        out.push('^');
+        out.push_str(&charge_text(charge));
+    }
+
+    /// The separator between the parts of an adduct
+    fn separator(&self, out: &mut String) {
+        out.push('*');
+    }
+
+    /// An electron, which is written with its charge
+    fn electron(&self, out: &mut String) {
+        out.push_str("e-");
+    }
+
+    fn state(&self, out: &mut String, state: State) {
+        out.push('(');
+        out.push_str(state.symbol());
+        out.push(')');
+    }
+
+    fn plus(&self, out: &mut String) {
+        push_spaced(out, self.options(), "+");
+    }
+
+    /// The arrow between the sides of an equation e.g. `->` or `<=>`
+    fn arrow(&self, out: &mut String, arrow: Arrow) {
+        push_spaced(out, self.options(), arrow.symbol());
+    }
+
+    /// An arrow with conditions, already rendered, above and below it e.g. `-[Fe, 450C]->`.
+    /// Either can be empty, but not both
+    fn conditioned_arrow(&self, out: &mut String, arrow: Arrow, above: &str, below: &str) {
+        let (left, right) = arrow.halves();
+        let mut symbol = format!("{}[{}]", left, above);
+        if !below.is_empty() {
+            symbol.push_str(&format!("[{}]", below));
+        }
+        symbol.push_str(right);
+        push_spaced(out, self.options(), &symbol);
+    }
+
+    /// A condition which is not a species e.g. `450C`
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(text);
+    }
+
+    /// LaTeX markup in a condition e.g. `\Delta`, written like text by formats which can't show it
+    fn markup(&self, out: &mut String, markup: &str) {
+        self.text(out, markup);
+    }
+
+    /// The separator between the conditions on one side of an arrow
+    fn condition_separator(&self, out: &mut String) {
+        out.push_str(", ");
+    }
+
+    /// Called with everything written for a node, e.g. to wrap it in a command
+    fn finish(&self, out: String) -> String {
+        out
+    }
+}
+
+/// The plain text format, which `parse` reads back into the same node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Text {
+    pub options: FormatOptions,
+}
+
+impl Text {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Renderer for Text {
+    fn options(&self) -> FormatOptions {
+        self.options
+    }
+}
+
+/// Unicode text with subscript and superscript digits and a real arrow e.g. `2H₂ + O₂ → 2H₂O`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unicode {
+    pub options: FormatOptions,
+    /// The arrow between the sides of an equation going forward, `→` by default
+    pub arrow: char,
+}
+
+impl Unicode {
+    pub fn new(options: FormatOptions) -> Self {
+        Self {
+            options,
+            arrow: '→',
+        }
+    }
+
+    /// Use a different forward arrow e.g. `⟶`
+    pub fn with_arrow(mut self, arrow: char) -> Self {
+        self.arrow = arrow;
+        self
+    }
+}
+
+impl Default for Unicode {
+    fn default() -> Self {
+        Self::new(FormatOptions {
+            spaced: true,
+            ..Default::default()
+        })
+    }
+}
+
+impl Renderer for Unicode {
+    fn options(&self) -> FormatOptions {
+        self.options
+    }
+
+    fn subscript(&self, out: &mut String, subscript: u16) {
+        out.push_str(&digits(subscript, SUBSCRIPT_DIGITS));
+    }
+
+    fn charge(&self, out: &mut String, charge: i16) {
+        if charge.unsigned_abs() != 1 {
+            out.push_str(&digits(charge.unsigned_abs(), SUPERSCRIPT_DIGITS));
+        }
+        out.push(if charge < 0 { '⁻' } else { '⁺' });
+    }
+
+    fn separator(&self, out: &mut String) {
+        out.push('·');
+    }
+
+    fn electron(&self, out: &mut String) {
+        out.push_str("e⁻");
+    }
+
+    fn arrow(&self, out: &mut String, arrow: Arrow) {
+        let symbol = match arrow {
+            Arrow::Forward => self.arrow,
+            arrow => arrow.unicode(),
+        };
+        push_spaced(out, self.options, &symbol.to_string());
+    }
+}
+
+/// mhchem markup for LaTeX e.g. `\ce{2H2 + O2 -> 2H2O}`.
+/// Plus signs and arrows always have spaces around them, which mhchem needs to tell a plus from a charge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Mhchem {
+    pub options: FormatOptions,
+}
+
+impl Mhchem {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Renderer for Mhchem {
+    fn options(&self) -> FormatOptions {
+        FormatOptions {
+            spaced: true,
+            ..self.options
+        }
+    }
+
+    fn open(&self, out: &mut String, kind: BracketKind) {
+        push_latex_bracket(out, kind, kind.open());
+    }
+
+    fn close(&self, out: &mut String, kind: BracketKind) {
+        push_latex_bracket(out, kind, kind.close());
+    }
+
+    fn charge(&self, out: &mut String, charge: i16) {
+        out.push_str(&format!("^{{{}}}", charge_text(charge)));
+    }
+
+    fn arrow(&self, out: &mut String, arrow: Arrow) {
+        push_spaced(out, self.options(), mhchem_arrow(arrow));
+    }
+
+    /// Conditions follow the arrow e.g. `->[Fe][450C]`.
+    /// mhchem's `=` is a bond which takes no conditions, so a conditioned `=` is written as `->`
+    fn conditioned_arrow(&self, out: &mut String, arrow: Arrow, above: &str, below: &str) {
+        let arrow = match arrow {
+            Arrow::Equals => Arrow::Forward,
+            arrow => arrow,
+        };
+        let mut symbol = format!("{}[{}]", mhchem_arrow(arrow), above);
+        if !below.is_empty() {
+            symbol.push_str(&format!("[{}]", below));
+        }
+        push_spaced(out, self.options(), &symbol);
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(&latex_escape(text));
+    }
+
+    fn markup(&self, out: &mut String, markup: &str) {
+        out.push_str(markup);
+    }
+
+    fn finish(&self, out: String) -> String {
+        format!("\\ce{{{}}}", out)
+    }
+}
+
+/// Plain LaTeX math e.g. `\mathrm{Fe}_{2}\mathrm{O}_{3}`, to be put inside a math environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latex {
+    pub options: FormatOptions,
+}
+
+impl Latex {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Default for Latex {
+    fn default() -> Self {
+        Self::new(FormatOptions {
+            spaced: true,
+            ..Default::default()
+        })
+    }
+}
+
+impl Renderer for Latex {
+    fn options(&self) -> FormatOptions {
+        self.options
+    }
+
+    fn element(&self, out: &mut String, symbol: &str) {
+        out.push_str(&format!("\\mathrm{{{}}}", symbol));
+    }
+
+    fn subscript(&self, out: &mut String, subscript: u16) {
+        out.push_str(&format!("_{{{}}}", subscript));
+    }
+
+    fn open(&self, out: &mut String, kind: BracketKind) {
+        push_latex_bracket(out, kind, kind.open());
+    }
+
+    fn close(&self, out: &mut String, kind: BracketKind) {
+        push_latex_bracket(out, kind, kind.close());
+    }
+
+    fn charge(&self, out: &mut String, charge: i16) {
+        out.push_str(&format!("^{{{}}}", charge_text(charge)));
+    }
+
+    fn separator(&self, out: &mut String) {
+        out.push_str("\\cdot ");
+    }
+
+    fn electron(&self, out: &mut String) {
+        out.push_str("\\mathrm{e}^{-}");
+    }
+
+    fn state(&self, out: &mut String, state: State) {
+        out.push_str(&format!("(\\mathrm{{{}}})", state.symbol()));
+    }
+
+    fn arrow(&self, out: &mut String, arrow: Arrow) {
+        push_spaced(out, self.options, latex_arrow(arrow));
+    }
+
+    /// Uses the extensible `\xrightarrow` and `\xleftarrow` where there is one
+    fn conditioned_arrow(&self, out: &mut String, arrow: Arrow, above: &str, below: &str) {
+        let symbol = match arrow {
+            Arrow::Forward | Arrow::Reverse => {
+                let command = match arrow {
+                    Arrow::Forward => "\\xrightarrow",
+                    _ => "\\xleftarrow",
+                };
+                match below {
+                    "" => format!("{}{{{}}}", command, above),
+                    below => format!("{}[{}]{{{}}}", command, below, above),
+                }
+            }
+            arrow => {
+                let symbol = match below {
+                    "" => latex_arrow(arrow).to_owned(),
+                    below => format!("\\underset{{{}}}{{{}}}", below, latex_arrow(arrow)),
+                };
+                format!("\\overset{{{}}}{{{}}}", above, symbol)
+            }
+        };
+        push_spaced(out, self.options, &symbol);
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(&format!("\\text{{{}}}", latex_escape(text)));
+    }
+
+    /// Written as is in math mode, unless it switches to math mode itself e.g. `$\Delta$`
+    fn markup(&self, out: &mut String, markup: &str) {
+        if markup.contains('$') {
+            out.push_str(&format!("\\text{{{}}}", markup));
+        } else {
+            out.push_str(markup);
+        }
+    }
+}
+
+/// The mhchem arrow for an arrow.
+/// mhchem's `<->` is a resonance arrow, `<-->` is the pair of arrows of a reversible reaction
+fn mhchem_arrow(arrow: Arrow) -> &'static str {
+    match arrow {
+        Arrow::Reversible => "<-->",
+        arrow => arrow.symbol(),
+    }
+}
+
+/// The LaTeX command for an arrow
+fn latex_arrow(arrow: Arrow) -> &'static str {
+    match arrow {
+        Arrow::Forward => "\\rightarrow",
+        Arrow::Reverse => "\\leftarrow",
+        Arrow::Reversible => "\\rightleftarrows",
+        Arrow::Equilibrium => "\\rightleftharpoons",
+        Arrow::Equals => "=",
+    }
+}
+
+/// Presentation MathML e.g. `<math …><mrow><msub><mi mathvariant="normal">O</mi><mn>2</mn></msub></mrow></math>`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MathMl {
+    pub options: FormatOptions,
+}
+
+impl MathMl {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Renderer for MathMl {
+    fn options(&self) -> FormatOptions {
+        self.options
+    }
+
+    fn coefficient(&self, out: &mut String, coefficient: u16) {
+        out.push_str(&format!("<mn>{}</mn>", coefficient));
+    }
+
+    fn element(&self, out: &mut String, symbol: &str) {
+        out.push_str(&format!(
+            "<mi mathvariant=\"normal\">{}</mi>",
+            escape(symbol)
+        ));
+    }
+
+    fn subscripted(&self, out: &mut String, base: &str, subscript: u16) {
+        out.push_str(&format!(
+            "<msub><mrow>{}</mrow><mn>{}</mn></msub>",
+            base, subscript
+        ));
+    }
+
+    fn open(&self, out: &mut String, kind: BracketKind) {
+        out.push_str(&format!("<mo>{}</mo>", kind.open()));
+    }
+
+    fn close(&self, out: &mut String, kind: BracketKind) {
+        out.push_str(&format!("<mo>{}</mo>", kind.close()));
+    }
+
+    fn charge(&self, out: &mut String, charge: i16) {
+        let sign = if charge < 0 { '−' } else { '+' };
+        let magnitude = match charge.unsigned_abs() {
+            1 => String::new(),
+            magnitude => format!("<mn>{}</mn>", magnitude),
+        };
+        out.push_str(&format!(
+            "<msup><mrow></mrow><mrow>{}<mo>{}</mo></mrow></msup>",
+            magnitude, sign
+        ));
+    }
+
+    fn separator(&self, out: &mut String) {
+        out.push_str("<mo>·</mo>");
+    }
+
+    fn electron(&self, out: &mut String) {
+        out.push_str(r#"<msup><mi mathvariant="normal">e</mi><mo>−</mo></msup>"#);
+    }
+
+    fn state(&self, out: &mut String, state: State) {
+        out.push_str(&format!(
+            "<mo>(</mo><mi mathvariant=\"normal\">{}</mi><mo>)</mo>",
+            state.symbol()
+        ));
+    }
+
+    fn plus(&self, out: &mut String) {
+        out.push_str("<mo>+</mo>");
+    }
+
+    fn arrow(&self, out: &mut String, arrow: Arrow) {
+        out.push_str(&format!("<mo>{}</mo>", arrow.unicode()));
+    }
+
+    fn conditioned_arrow(&self, out: &mut String, arrow: Arrow, above: &str, below: &str) {
+        match below {
+            "" => out.push_str(&format!(
+                "<mover><mo>{}</mo><mrow>{}</mrow></mover>",
+                arrow.unicode(),
+                above
+            )),
+            below => out.push_str(&format!(
+                "<munderover><mo>{}</mo><mrow>{}</mrow><mrow>{}</mrow></munderover>",
+                arrow.unicode(),
+                below,
+                above
+            )),
+        }
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(&format!("<mtext>{}</mtext>", escape(text)));
+    }
+
+    fn condition_separator(&self, out: &mut String) {
+        out.push_str("<mo>,</mo>");
+    }
+
+    fn finish(&self, out: String) -> String {
+        format!(
+            "<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>{}</mrow></math>",
+            out
+        )
+    }
+}
+
+/// HTML with `<sub>` and `<sup>` tags e.g. `2H<sub>2</sub> + O<sub>2</sub> → 2H<sub>2</sub>O`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Html {
+    pub options: FormatOptions,
+}
+
+impl Html {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl Default for Html {
+    fn default() -> Self {
+        Self::new(FormatOptions {
+            spaced: true,
+            ..Default::default()
+        })
+    }
+}
+
+impl Renderer for Html {
+    fn options(&self) -> FormatOptions {
+        self.options
+    }
+
+    fn element(&self, out: &mut String, symbol: &str) {
+        out.push_str(&escape(symbol));
+    }
+
+    fn subscript(&self, out: &mut String, subscript: u16) {
+        out.push_str(&format!("<sub>{}</sub>", subscript));
+    }
+
+    fn charge(&self, out: &mut String, charge: i16) {
+        out.push_str(&format!(
+            "<sup>{}</sup>",
+            charge_text(charge).replace('-', "−")
+        ));
+    }
+
+    fn separator(&self, out: &mut String) {
+        out.push('·');
+    }
+
+    fn electron(&self, out: &mut String) {
+        out.push_str("e<sup>−</sup>");
+    }
+
+    fn arrow(&self, out: &mut String, arrow: Arrow) {
+        push_spaced(out, self.options, &arrow.unicode().to_string());
+    }
+
+    /// Conditions are written after the arrow e.g. `→<sup>Fe</sup><sub>450C</sub>`
+    fn conditioned_arrow(&self, out: &mut String, arrow: Arrow, above: &str, below: &str) {
+        let mut symbol = arrow.unicode().to_string();
+        if !above.is_empty() {
+            symbol.push_str(&format!("<sup>{}</sup>", above));
+        }
+        if !below.is_empty() {
+            symbol.push_str(&format!("<sub>{}</sub>", below));
+        }
+        push_spaced(out, self.options, &symbol);
+    }
+
+    fn text(&self, out: &mut String, text: &str) {
+        out.push_str(&escape(text));
+    }
+}
+
+/// Escape text for HTML and MathML
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for val in text.chars() {
+        match val {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(val),
+        }
+    }
+    escaped
+}
+
+/// Escape text for LaTeX and mhchem
+fn latex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for val in text.chars() {
+        match LATEX_ESCAPES.iter().find(|(special, _)| *special == val) {
+            Some((_, escape)) => escaped.push_str(escape),
+            None => escaped.push(val),
+        }
+    }
+    escaped
+}
+
+/// Write a bracket for LaTeX, where plain braces group rather than being shown so they are escaped
+fn push_latex_bracket(out: &mut String, kind: BracketKind, bracket: char) {
+    if kind == BracketKind::Curly {
+        out.push('\\');
+    }
+    out.push(bracket);
+}
+
+/// Render a node with a renderer
+pub fn render<R: Renderer + ?Sized>(renderer: &R, node: &Node) -> String {
+    let mut out = String::new();
+    match node {
+        Node::Formula(unit) => formula_unit(renderer, &mut out, unit),
+        Node::Equation(equation) => self::equation(renderer, &mut out, equation),
+    }
+    renderer.finish(out)
+}
+
+/// The digits and sign of a charge, leaving out the digit of ±1 e.g. `2-` or `+`
+pub(crate) fn charge_text(charge: i16) -> String {
+    let sign = if charge < 0 { '-' } else { '+' };
+    match charge.unsigned_abs() {
+        1 => sign.to_string(),
+        magnitude => format!("{}{}", magnitude, sign),
+    }
+}
+
+/// Write a plus sign or arrow, with spaces around it if the options ask for them
+pub(crate) fn push_spaced(out: &mut String, options: FormatOptions, symbol: &str) {
+    if options.spaced {
+        out.push(' ');
+        out.push_str(symbol);
+        out.push(' ');
+    } else {
+        out.push_str(symbol);
+    }
+}
+
+fn equation<R: Renderer + ?Sized>(renderer: &R, out: &mut String, equation: &Equation) {
+    side(renderer, out, equation.reactants());
+    if equation.above().is_empty() && equation.below().is_empty() {
+        renderer.arrow(out, equation.arrow());
+    } else {
+        let above = conditions(renderer, equation.above());
+        let below = conditions(renderer, equation.below());
+        renderer.conditioned_arrow(out, equation.arrow(), &above, &below);
+    }
+    side(renderer, out, equation.products());
+}
+
+/// Render the conditions on one side of an arrow on their own
+fn conditions<R: Renderer + ?Sized>(renderer: &R, conditions: &[Condition]) -> String {
+    let mut out = String::new();
+    for (index, condition) in conditions.iter().enumerate() {
+        if index > 0 {
+            renderer.condition_separator(&mut out);
+        }
+        match condition {
+            Condition::Species(unit) => formula_unit(renderer, &mut out, unit),
+            Condition::Markup(markup, _) => renderer.markup(&mut out, markup),
+            Condition::Text(text, _) => renderer.text(&mut out, text),
+        }
+    }
+    out
+}
+
+fn side<R: Renderer + ?Sized>(renderer: &R, out: &mut String, side: &Side) {
+    for (index, unit) in side.units().iter().enumerate() {
+        if index > 0 {
+            renderer.plus(out);
+        }
+        formula_unit(renderer, out, unit);
+    }
+}
+
+fn formula_unit<R: Renderer + ?Sized>(renderer: &R, out: &mut String, unit: &FormulaUnit) {
+    coefficient(renderer, out, unit.coefficient());
+    if unit.is_electron() {
+        renderer.electron(out);
+    }
+    for (index, part) in unit.parts().iter().enumerate() {
+        if index > 0 {
+            renderer.separator(out);
+            coefficient(renderer, out, part.count());
+        }
+        self::part(renderer, out, part);
+    }
+    if let Some(state) = unit.state() {
+        renderer.state(out, state);
+    }
+}
+
+fn part<R: Renderer + ?Sized>(renderer: &R, out: &mut String, part: &Part) {
+    terms(renderer, out, part.terms());
+    if part.charge() != 0 {
+        renderer.charge(out, part.charge());
+    }
+}
+
+fn terms<R: Renderer + ?Sized>(renderer: &R, out: &mut String, terms: &[Term]) {
+    for term in terms {
+        match term {
+            Term::Element(element) => {
+                let mut base = String::new();
+                renderer.element(&mut base, element.symbol());
+                subscripted(renderer, out, base, element.count());
+            }
+            Term::Group(group) => self::group(renderer, out, group),
+        }
+    }
+}
+
+fn group<R: Renderer + ?Sized>(renderer: &R, out: &mut String, group: &Group) {
+    let mut base = String::new();
+    renderer.open(&mut base, group.kind());
+    terms(renderer, &mut base, group.terms());
+    if group.charge() != 0 {
+        renderer.charge(&mut base, group.charge());
+    }
+    renderer.close(&mut base, group.kind());
+    subscripted(renderer, out, base, group.subscript());
+}
+
+/// Write a coefficient, unless it is a 1 which should be left out
+fn coefficient<R: Renderer + ?Sized>(renderer: &R, out: &mut String, coefficient: u16) {
+    if coefficient != 1 || !renderer.options().omit_ones {
+        renderer.coefficient(out, coefficient);
+    }
+}
+
+/// Write an element or group with its subscript, unless it is a 1 which should be left out
+fn subscripted<R: Renderer + ?Sized>(renderer: &R, out: &mut String, base: String, subscript: u16) {
+    if subscript != 1 || !renderer.options().omit_ones {
+        renderer.subscripted(out, &base, subscript);
+    } else {
+        out.push_str(&base);
+    }
+}
+
+impl Node {
+    /// Format the node as plain text with options, see `render::Text`
+    pub fn format(&self, options: FormatOptions) -> String {
+        render(&Text::new(options), self)
+    }
+
+    /// Format the node as Unicode text, see `render::Unicode`
+    pub fn to_unicode(&self) -> String {
+        render(&Unicode::default(), self)
+    }
+
+    /// Format the node as mhchem markup, see `render::Mhchem`
+    pub fn to_mhchem(&self) -> String {
+        render(&Mhchem::default(), self)
+    }
+
+    /// Format the node as LaTeX math, see `render::Latex`
+    pub fn to_latex(&self) -> String {
+        render(&Latex::default(), self)
+    }
+
+    /// Format the node as presentation MathML, see `render::MathMl`
+    pub fn to_mathml(&self) -> String {
+        render(&MathMl::default(), self)
+    }
+
+    /// Format the node as HTML, see `render::Html`
+    pub fn to_html(&self) -> String {
+        render(&Html::default(), self)
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", render(&Text::default(), self))
+    }
+}
+
+impl Display for FormulaUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        formula_unit(&Text::default(), &mut out, self);
+        write!(f, "{}", out)
+    }
+}
+
+impl Display for Equation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        equation(&Text::default(), &mut out, self);
+        write!(f, "{}", out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast_types::ElementCount, parse, parse_formula, parse_mhchem};
+
+    fn round_trip(source: &str, options: FormatOptions) -> String {
+        let node = parse(source.to_owned()).unwrap();
+        let text = node.format(options);
+        assert_eq!(Ok(node), parse(text.clone()), "parsing {text}");
+        text
+    }
+
+    #[test]
+    fn formats_canonical_text() {
+        let cases = [
+            ("2Fe2O3", "2Fe2O3"),
+            ("4Fe + 3O2 -> 2Fe2O3", "4Fe+3O2->2Fe2O3"),
+            ("Ca(OH)2", "Ca(OH)2"),
+            ("[Cu{NH3}4]SO4", "[Cu{NH3}4]SO4"),
+            ("SO4^2-", "SO4^2-"),
+            ("NH4+", "NH4^+"),
+            ("Ca(NO3^-)2^2+(aq)", "Ca(NO3^-)2^2+(aq)"),
+            ("2CuSO4·5H2O(s)", "2CuSO4*5H2O(s)"),
+            ("Cu+2Ag^+->Cu^2++2Ag", "Cu+2Ag^+->Cu^2++2Ag"),
+        ];
+        for (source, exp) in cases {
+            assert_eq!(exp, round_trip(source, FormatOptions::default()));
+        }
+    }
+
+    #[test]
+    fn can_space_equations() {
+        let options = FormatOptions {
+            spaced: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            "2H2 + O2 -> 2H2O(l)",
+            round_trip("2H2+O2->2H2O(l)", options)
+        );
+    }
+
+    #[test]
+    fn can_keep_ones() {
+        let options = FormatOptions {
+            omit_ones: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            "1Ca1(O1H1)2+2H1Cl1->1Ca1Cl2+2H2O1",
+            round_trip("Ca(OH)2+2HCl->CaCl2+2H2O", options)
+        );
+        assert_eq!("1Cu1S1O4*5H2O1", round_trip("CuSO4*5H2O", options));
+    }
+
+    #[test]
+    fn displays_typed_nodes() {
+        let unit = parse_formula(String::from("3(NH4)2SO4")).unwrap();
+        assert_eq!("3(NH4)2SO4", unit.to_string());
+
+        let node = parse(String::from("H2 + Cl2 -> 2HCl")).unwrap();
+        assert_eq!("H2+Cl2->2HCl", node.to_string());
+    }
+
+    #[test]
+    fn renders_unicode() {
+        let cases = [
+            ("Fe2O3", "Fe₂O₃"),
+            ("SO4^2-", "SO₄²⁻"),
+            ("NH4+", "NH₄⁺"),
+            ("2H2+O2->2H2O", "2H₂ + O₂ → 2H₂O"),
+            ("Ca(NO3^-)2(aq)", "Ca(NO₃⁻)₂(aq)"),
+            ("CuSO4*5H2O", "CuSO₄·5H₂O"),
+            ("C12H22O11", "C₁₂H₂₂O₁₁"),
+        ];
+        for (source, exp) in cases {
+            assert_eq!(exp, parse(source.to_owned()).unwrap().to_unicode());
+        }
+    }
+
+    #[test]
+    fn parses_unicode_back() {
+        for source in [
+            "2H2+O2->2H2O",
+            "Ca(NO3^-)2^2+(aq)",
+            "2CuSO4*5H2O(s)",
+            "SO4^12-",
+        ] {
+            let node = parse(source.to_owned()).unwrap();
+            assert_eq!(Ok(node.clone()), parse(node.to_unicode()));
+        }
+    }
+
+    #[test]
+    fn can_choose_the_unicode_arrow() {
+        let node = parse(String::from("2H2+O2->2H2O")).unwrap();
+        let renderer = Unicode::new(FormatOptions::default()).with_arrow('⟶');
+        assert_eq!("2H₂+O₂⟶2H₂O", render(&renderer, &node));
+    }
+
+    #[test]
+    fn renders_arrow_kinds() {
+        let cases = [
+            ("N2+3H2<=>2NH3", "N2+3H2<=>2NH3", "N₂ + 3H₂ ⇌ 2NH₃"),
+            ("H2O<->H^++OH^-", "H2O<->H^++OH^-", "H₂O ⇄ H⁺ + OH⁻"),
+            ("2H2O<-2H2+O2", "2H2O<-2H2+O2", "2H₂O ← 2H₂ + O₂"),
+            ("NaCl=Na^++Cl^-", "NaCl=Na^++Cl^-", "NaCl = Na⁺ + Cl⁻"),
+        ];
+        for (source, text, unicode) in cases {
+            let node = parse(source.to_owned()).unwrap();
+            assert_eq!(text, node.to_string());
+            assert_eq!(unicode, node.to_unicode());
+            assert_eq!(Ok(node.clone()), parse(node.to_unicode()));
+        }
+
+        let node = parse(String::from("N2+3H2<=>2NH3")).unwrap();
+        assert_eq!(r"\ce{N2 + 3H2 <=> 2NH3}", node.to_mhchem());
+        assert!(node.to_latex().contains(r" \rightleftharpoons "));
+        assert!(node.to_mathml().contains("<mo>⇌</mo>"));
+        assert_eq!(
+            "N<sub>2</sub> + 3H<sub>2</sub> ⇌ 2NH<sub>3</sub>",
+            node.to_html()
+        );
+
+        let node = parse(String::from("H2O<->H^++OH^-")).unwrap();
+        assert_eq!(r"\ce{H2O <--> H^{+} + OH^{-}}", node.to_mhchem());
+    }
+
+    #[test]
+    fn renders_conditions() {
+        let node = parse(String::from("2KClO3-[MnO2, 1.5 atm][heat]->2KCl+3O2")).unwrap();
+        assert_eq!("2KClO3-[MnO2, 1.5 atm][heat]->2KCl+3O2", node.to_string());
+        assert_eq!(
+            "2KClO₃ -[MnO₂, 1.5 atm][heat]-> 2KCl + 3O₂",
+            node.to_unicode()
+        );
+        assert_eq!(Ok(node.clone()), parse(node.to_unicode()));
+        assert_eq!(
+            r"\ce{2KClO3 ->[MnO2, 1.5 atm][heat] 2KCl + 3O2}",
+            node.to_mhchem()
+        );
+        assert!(node
+            .to_latex()
+            .contains(r"\xrightarrow[\text{heat}]{\mathrm{Mn}\mathrm{O}_{2}, \text{1.5 atm}}"));
+        assert!(node.to_mathml().contains(concat!(
+            "<munderover><mo>→</mo><mrow><mtext>heat</mtext></mrow>",
+            r#"<mrow><mi mathvariant="normal">Mn</mi>"#,
+            r#"<msub><mrow><mi mathvariant="normal">O</mi></mrow><mn>2</mn></msub>"#,
+            "<mo>,</mo><mtext>1.5 atm</mtext></mrow></munderover>"
+        )));
+        assert!(node
+            .to_html()
+            .contains(" →<sup>MnO<sub>2</sub>, 1.5 atm</sup><sub>heat</sub> "));
+
+        let node = parse(String::from("N2+3H2<=[Fe]=>2NH3")).unwrap();
+        assert_eq!("N2+3H2<=[Fe]=>2NH3", node.to_string());
+        assert_eq!(r"\ce{N2 + 3H2 <=>[Fe] 2NH3}", node.to_mhchem());
+        assert!(node
+            .to_latex()
+            .contains(r"\overset{\mathrm{Fe}}{\rightleftharpoons}"));
+    }
+
+    #[test]
+    fn escapes_conditions() {
+        let node = parse(String::from("A-[<b> & 'x']->B")).unwrap();
+        assert!(node
+            .to_html()
+            .contains("→<sup>&lt;b&gt; &amp; &#39;x&#39;</sup>"));
+        assert!(node
+            .to_mathml()
+            .contains("<mtext>&lt;b&gt; &amp; &#39;x&#39;</mtext>"));
+
+        let node = parse(String::from("A-[50% H2SO4 & heat_1]->B")).unwrap();
+        assert!(node
+            .to_latex()
+            .contains(r"\xrightarrow{\text{50\% H2SO4 \& heat\_1}}"));
+        assert_eq!(r"\ce{A ->[50\% H2SO4 \& heat\_1] B}", node.to_mhchem());
+        assert_eq!(Ok(node.clone()), parse_mhchem(node.to_mhchem()));
+
+        // LaTeX commands are kept, as markup rather than text
+        let node = parse_mhchem(String::from(r"\ce{A ->[\Delta][$\Delta$] B}")).unwrap();
+        assert_eq!(r"\ce{A ->[\Delta][$\Delta$] B}", node.to_mhchem());
+        assert!(node
+            .to_latex()
+            .contains(r"\xrightarrow[\text{$\Delta$}]{\Delta}"));
+        assert_eq!(Ok(node.clone()), parse_mhchem(node.to_mhchem()));
+        assert_eq!(Ok(node.clone()), parse(node.to_string()));
+
+        let node = parse(String::from(r"A-[{~^\}]->B")).unwrap();
+        assert!(node
+            .to_latex()
+            .contains(r"\text{\{\textasciitilde{}\textasciicircum{}\textbackslash{}\}}"));
+    }
+
+    #[test]
+    fn renders_electrons() {
+        let node = parse(String::from("Cu^2+ + 2e- -> Cu")).unwrap();
+        assert_eq!("Cu^2++2e-->Cu", node.to_string());
+        assert_eq!("Cu²⁺ + 2e⁻ → Cu", node.to_unicode());
+        assert_eq!(r"\ce{Cu^{2+} + 2e- -> Cu}", node.to_mhchem());
+        assert!(node.to_latex().contains(r" + 2\mathrm{e}^{-} "));
+        assert!(node
+            .to_mathml()
+            .contains(r#"<mn>2</mn><msup><mi mathvariant="normal">e</mi><mo>−</mo></msup>"#));
+        assert_eq!("Cu<sup>2+</sup> + 2e<sup>−</sup> → Cu", node.to_html());
+        for text in [node.to_string(), node.to_unicode()] {
+            assert_eq!(Ok(node.clone()), parse(text));
+        }
+    }
+
+    #[test]
+    fn renders_mhchem() {
+        let cases = [
+            ("2H2+O2->2H2O", r"\ce{2H2 + O2 -> 2H2O}"),
+            ("SO4^2-", r"\ce{SO4^{2-}}"),
+            ("Ca(NO3^-)2(aq)", r"\ce{Ca(NO3^{-})2(aq)}"),
+            ("[Cu{NH3}4]SO4", r"\ce{[Cu\{NH3\}4]SO4}"),
+            ("CuSO4·5H2O(s)", r"\ce{CuSO4*5H2O(s)}"),
+        ];
+        for (source, exp) in cases {
+            assert_eq!(exp, parse(source.to_owned()).unwrap().to_mhchem());
+        }
+    }
+
+    #[test]
+    fn renders_latex() {
+        let cases = [
+            ("Fe2O3", r"\mathrm{Fe}_{2}\mathrm{O}_{3}"),
+            ("NH4+(aq)", r"\mathrm{N}\mathrm{H}_{4}^{+}(\mathrm{aq})"),
+            (
+                "2H2+O2->2H2O",
+                r"2\mathrm{H}_{2} + \mathrm{O}_{2} \rightarrow 2\mathrm{H}_{2}\mathrm{O}",
+            ),
+            ("Ca(OH)2", r"\mathrm{Ca}(\mathrm{O}\mathrm{H})_{2}"),
+            (
+                "CuSO4*5H2O",
+                r"\mathrm{Cu}\mathrm{S}\mathrm{O}_{4}\cdot 5\mathrm{H}_{2}\mathrm{O}",
+            ),
+        ];
+        for (source, exp) in cases {
+            assert_eq!(exp, parse(source.to_owned()).unwrap().to_latex());
+        }
+    }
+
+    #[test]
+    fn renders_html() {
+        let cases = [
+            ("Fe2O3", "Fe<sub>2</sub>O<sub>3</sub>"),
+            ("SO4^2-", "SO<sub>4</sub><sup>2−</sup>"),
+            (
+                "2H2+O2->2H2O(l)",
+                "2H<sub>2</sub> + O<sub>2</sub> → 2H<sub>2</sub>O(l)",
+            ),
+            ("Ca(OH)2", "Ca(OH)<sub>2</sub>"),
+            ("CuSO4*5H2O", "CuSO<sub>4</sub>·5H<sub>2</sub>O"),
+        ];
+        for (source, exp) in cases {
+            assert_eq!(exp, parse(source.to_owned()).unwrap().to_html());
+        }
+    }
+
+    #[test]
+    fn renders_mathml() {
+        let node = parse(String::from("2Ca(OH)2^+")).unwrap();
+        let exp = concat!(
+            r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow>"#,
+            "<mn>2</mn>",
+            r#"<mi mathvariant="normal">Ca</mi>"#,
+            "<msub><mrow><mo>(</mo>",
+            r#"<mi mathvariant="normal">O</mi><mi mathvariant="normal">H</mi>"#,
+            "<mo>)</mo></mrow><mn>2</mn></msub>",
+            "<msup><mrow></mrow><mrow><mo>+</mo></mrow></msup>",
+            "</mrow></math>"
+        );
+        assert_eq!(exp, node.to_mathml());
+
+        let node = parse(String::from("H2->H^+")).unwrap();
+        let exp = concat!(
+            r#"<math xmlns="http://www.w3.org/1998/Math/MathML"><mrow>"#,
+            r#"<msub><mrow><mi mathvariant="normal">H</mi></mrow><mn>2</mn></msub>"#,
+            "<mo>→</mo>",
+            r#"<mi mathvariant="normal">H</mi>"#,
+            "<msup><mrow></mrow><mrow><mo>+</mo></mrow></msup>",
+            "</mrow></math>"
+        );
+        assert_eq!(exp, node.to_mathml());
+    }
+
+    #[test]
+    fn escapes_markup() {
+        let unit = FormulaUnit::new(
+            1,
+            vec![Term::Element(ElementCount::new("<b>&", 2))],
+            0,
+            None,
+        )
+        .unwrap();
+        let node = Node::Formula(unit);
+        assert_eq!("&lt;b&gt;&amp;<sub>2</sub>", node.to_html());
+        assert!(node
+            .to_mathml()
+            .contains(r#"<mi mathvariant="normal">&lt;b&gt;&amp;</mi>"#));
+    }
+}
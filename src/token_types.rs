@@ -1,7 +1,11 @@
+use crate::{
+    ast_types::{Arrow, BracketKind, State},
+    error::Span,
+};
 use core::fmt::Debug;
 
 /// A parenthesis type.
-/// '(' is `OPEN` and ')' is `CLOSE`
+/// '(', '[' and '{' are `OPEN`, ')', ']' and '}' are `CLOSE`
 #[derive(Debug, PartialEq)]
 pub enum ParenType {
     OPEN,
@@ -15,15 +19,31 @@ pub enum Tokens {
     Element { data: String, meta: TokenMetadata },
     /// Numbers e.g. 13. The data is 13u16
     Number { data: u16, meta: TokenMetadata },
-    /// Parenthesis e.g. ) The data is ParenType::CLOSE
+    /// Parenthesis e.g. ] The data is ParenType::CLOSE and the kind is BracketKind::Square
     Paren {
         data: ParenType,
+        kind: BracketKind,
         meta: TokenMetadata,
     },
+    /// Electron e- or e⁻
+    Electron { meta: TokenMetadata },
     /// Plus sign +
     Plus { meta: TokenMetadata },
-    /// Yields sign ->
-    Yields { meta: TokenMetadata },
+    /// Yields sign e.g. <=>. The data is Arrow::Equilibrium
+    Yields { data: Arrow, meta: TokenMetadata },
+    /// One of the comma separated conditions of an arrow e.g. 450C in -[Fe, 450C]->.
+    /// The data is "450C", `below` is whether it was in the second brackets
+    Condition {
+        data: String,
+        below: bool,
+        meta: TokenMetadata,
+    },
+    /// Hydrate or adduct separator, · or *
+    Separator { meta: TokenMetadata },
+    /// Ionic charge e.g. ^2- or a trailing +. The data is -2i16
+    Charge { data: i16, meta: TokenMetadata },
+    /// State of matter e.g. (aq). The data is State::Aqueous
+    State { data: State, meta: TokenMetadata },
 }
 
 impl Tokens {
@@ -31,9 +51,29 @@ impl Tokens {
         match self {
             Self::Element { meta, data: _ } => meta,
             Self::Number { meta, data: _ } => meta,
-            Self::Paren { meta, data: _ } => meta,
+            Self::Paren { meta, .. } => meta,
+            Self::Electron { meta } => meta,
             Self::Plus { meta } => meta,
-            Self::Yields { meta } => meta,
+            Self::Yields { meta, data: _ } => meta,
+            Self::Condition { meta, .. } => meta,
+            Self::Separator { meta } => meta,
+            Self::Charge { meta, data: _ } => meta,
+            Self::State { meta, data: _ } => meta,
+        }
+    }
+
+    pub(crate) fn meta_mut(&mut self) -> &mut TokenMetadata {
+        match self {
+            Self::Element { meta, .. }
+            | Self::Number { meta, .. }
+            | Self::Paren { meta, .. }
+            | Self::Electron { meta }
+            | Self::Plus { meta }
+            | Self::Yields { meta, .. }
+            | Self::Condition { meta, .. }
+            | Self::Separator { meta }
+            | Self::Charge { meta, .. }
+            | Self::State { meta, .. } => meta,
         }
     }
 }
@@ -64,15 +104,60 @@ impl PartialEq for Tokens {
             (
                 Self::Paren {
                     data: l_data,
+                    kind: l_kind,
                     meta: _,
                 },
                 Self::Paren {
                     data: r_data,
+                    kind: r_kind,
                     meta: _,
                 },
-            ) => l_data == r_data,
+            ) => l_data == r_data && l_kind == r_kind,
+            (Self::Electron { meta: _ }, Self::Electron { meta: _ }) => true,
             (Self::Plus { meta: _ }, Self::Plus { meta: _ }) => true,
-            (Self::Yields { meta: _ }, Self::Yields { meta: _ }) => true,
+            (
+                Self::Yields {
+                    data: l_data,
+                    meta: _,
+                },
+                Self::Yields {
+                    data: r_data,
+                    meta: _,
+                },
+            ) => l_data == r_data,
+            (
+                Self::Condition {
+                    data: l_data,
+                    below: l_below,
+                    meta: _,
+                },
+                Self::Condition {
+                    data: r_data,
+                    below: r_below,
+                    meta: _,
+                },
+            ) => l_data == r_data && l_below == r_below,
+            (Self::Separator { meta: _ }, Self::Separator { meta: _ }) => true,
+            (
+                Self::Charge {
+                    data: l_data,
+                    meta: _,
+                },
+                Self::Charge {
+                    data: r_data,
+                    meta: _,
+                },
+            ) => l_data == r_data,
+            (
+                Self::State {
+                    data: l_data,
+                    meta: _,
+                },
+                Self::State {
+                    data: r_data,
+                    meta: _,
+                },
+            ) => l_data == r_data,
             _ => false,
         }
     }
@@ -94,7 +179,12 @@ impl TokenMetadata {
     pub fn raw(&self) -> &String {
         &self.raw
     }
+    /// The byte offset of the token in the parsed string
     pub fn loc(&self) -> usize {
         self.location
     }
+    /// The bytes the token covers in the parsed string
+    pub fn span(&self) -> Span {
+        Span::new(self.location, self.location + self.raw.len())
+    }
 }
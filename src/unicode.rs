@@ -0,0 +1,32 @@
+//! The Unicode subscript and superscript digits, read by the lexer and written by the renderers
+
+/// ₀ to ₉
+pub(crate) const SUBSCRIPT_DIGITS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+/// ⁰ to ⁹
+pub(crate) const SUPERSCRIPT_DIGITS: [char; 10] =
+    ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+/// The value of a subscript digit e.g. 2 for ₂
+pub(crate) fn subscript_digit(val: char) -> Option<u32> {
+    digit_value(val, SUBSCRIPT_DIGITS)
+}
+
+/// The value of a superscript digit e.g. 2 for ²
+pub(crate) fn superscript_digit(val: char) -> Option<u32> {
+    digit_value(val, SUPERSCRIPT_DIGITS)
+}
+
+/// The decimal digits of a number, written with a set of digit characters
+pub(crate) fn digits(number: u16, set: [char; 10]) -> String {
+    number
+        .to_string()
+        .chars()
+        .map(|digit| set[digit.to_digit(10).unwrap() as usize])
+        .collect()
+}
+
+fn digit_value(val: char, set: [char; 10]) -> Option<u32> {
+    set.iter()
+        .position(|digit| *digit == val)
+        .map(|value| value as u32)
+}